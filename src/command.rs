@@ -0,0 +1,15 @@
+// 界面命令：页面只负责产生命令，由 Application 统一执行，页面不直接操作后台任务
+
+use crate::{router::Route, settings::Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    StartBattle,              // 用当前军队开始战斗
+    ToggleAuto,               // 切换自动战斗：一场结束后自动开始下一场
+    CycleSpeed,               // 切换战斗速度：1x -> 2x -> 4x -> 跳过
+    Push(Route),              // 进入新页面
+    Pop,                      // 返回上一页；离开战斗页时中止战斗
+    Replace(Route),           // 替换当前页面
+    SaveFormation,            // 保存阵型页编辑的阵型
+    UpdateSettings(Settings), // 应用并保存设置
+}
@@ -0,0 +1,175 @@
+// 战斗动画调度：每个事件按序号只消费一次，生成的动画按单位保存
+// 同一单位可以同时播放多个动画（如连续受击时多个伤害飘字叠在一起）
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use egui::Color32;
+
+use crate::{
+    core::{batttle::BattleEvent, delivery::EventHistory},
+    i18n::tr,
+    model::UnitId,
+    settings::AnimQuality,
+};
+
+const ATK_DURATION: Duration = Duration::from_millis(400); // 出手
+const HIT_DURATION: Duration = Duration::from_millis(300); // 受击闪烁
+const POPUP_DURATION: Duration = Duration::from_millis(1000); // 飘字
+// 同一单位同时显示的飘字错开的层数
+const POPUP_SLOTS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub enum AnimKind {
+    Atk,          // 出手：向对方冲一下
+    Hit,          // 受击：血条闪烁
+    Popup(Popup), // 飘字
+}
+
+#[derive(Debug, Clone)]
+pub struct Anim {
+    pub kind: AnimKind,
+    pub start: Instant,
+    pub duration: Duration,
+}
+impl Anim {
+    // 播放进度 0~1
+    pub fn progress(&self, now: Instant) -> f32 {
+        (now.saturating_duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32())
+            .clamp(0.0, 1.0)
+    }
+
+    fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
+
+// 飘字内容
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub text: String,
+    pub color: Color32,
+    pub size: f32,
+    pub slot: usize, // 错开的层，避免同时出现的飘字完全重叠
+}
+impl Popup {
+    fn new(text: String, color: Color32) -> Self {
+        Self {
+            text,
+            color,
+            size: 14.0,
+            slot: 0,
+        }
+    }
+    fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AnimScheduler {
+    quality: AnimQuality,              // 动画质量，Off 时事件只消费不播放
+    last_seq: u64,                     // 已消费的最后一个事件序号
+    anims: HashMap<UnitId, Vec<Anim>>, // 单位 -> 正在播放的动画
+}
+impl AnimScheduler {
+    pub fn set_quality(&mut self, quality: AnimQuality) {
+        self.quality = quality;
+        if quality == AnimQuality::Off {
+            self.anims.clear();
+        }
+    }
+
+    // 消费新事件生成动画，speed 为战斗速度倍率，动画时长随之缩短
+    pub fn feed(&mut self, events: &EventHistory, speed: f32) {
+        for item in events.since(self.last_seq) {
+            self.last_seq = item.seq;
+            if self.quality == AnimQuality::Off {
+                continue;
+            }
+            let scale = |d: Duration| d.div_f32(speed);
+            let anim = |kind, duration| Anim {
+                kind,
+                start: item.at,
+                duration: scale(duration),
+            };
+            let popup = |p: Popup| anim(AnimKind::Popup(p), POPUP_DURATION);
+            let new: Vec<Anim> = match &item.event {
+                BattleEvent::ATK { .. } => vec![anim(AnimKind::Atk, ATK_DURATION)],
+                BattleEvent::DEF { amount, .. } => vec![
+                    anim(AnimKind::Hit, HIT_DURATION),
+                    popup(Popup::new(
+                        amount.to_string(),
+                        Color32::from_rgb(255, 50, 50),
+                    )),
+                ],
+                BattleEvent::Crit { amount, .. } => vec![
+                    anim(AnimKind::Hit, HIT_DURATION),
+                    popup(
+                        Popup::new(
+                            format!("{} {amount}", tr("CRIT")),
+                            Color32::from_rgb(255, 140, 0),
+                        )
+                        .size(20.0),
+                    ),
+                ],
+                BattleEvent::Miss { .. } => vec![popup(Popup::new(
+                    tr("MISS").to_string(),
+                    Color32::from_rgb(120, 120, 120),
+                ))],
+                BattleEvent::Dodge { .. } => vec![popup(Popup::new(
+                    tr("DODGE").to_string(),
+                    Color32::from_rgb(60, 120, 220),
+                ))],
+                BattleEvent::Heal { amount, .. } => vec![popup(Popup::new(
+                    format!("+{amount}"),
+                    Color32::from_rgb(40, 180, 60),
+                ))],
+            };
+            let list = self.anims.entry(item.event.id()).or_default();
+            // 低质量不显示飘字
+            let new = new.into_iter().filter(|a| {
+                self.quality == AnimQuality::High || !matches!(a.kind, AnimKind::Popup(_))
+            });
+            for mut a in new {
+                if let AnimKind::Popup(p) = &mut a.kind {
+                    let active = list
+                        .iter()
+                        .filter(|a| matches!(a.kind, AnimKind::Popup(_)))
+                        .count();
+                    p.slot = active % POPUP_SLOTS;
+                }
+                list.push(a);
+            }
+        }
+    }
+
+    // 移除播放完的动画
+    pub fn prune(&mut self, now: Instant) {
+        self.anims.retain(|_, list| {
+            list.retain(|a| !a.is_done(now));
+            !list.is_empty()
+        });
+    }
+
+    pub fn get(&self, id: UnitId) -> &[Anim] {
+        self.anims.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    // 正在播放的动画数量
+    pub fn len(&self) -> usize {
+        self.anims.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anims.is_empty()
+    }
+
+    // 换场时清空，已消费序号保留
+    pub fn clear(&mut self) {
+        self.anims.clear();
+    }
+}
@@ -0,0 +1,188 @@
+// 阵型编辑页：拖动单位调整所在列和行，最多 max_cols 列，每次放下后保存
+
+use std::collections::VecDeque;
+
+use egui::{
+    Area, Color32, Id, Order, Rect, ScrollArea, Sense, Stroke, StrokeKind, Ui, UiBuilder, Vec2,
+    pos2, scroll_area::ScrollSource, vec2,
+};
+
+use crate::{
+    UiExt,
+    command::Command,
+    components::{
+        page_header,
+        unit_ui::{self, ArmyType},
+    },
+    i18n::tr,
+    model::{Formation, Unit},
+};
+
+// 页面状态：编辑中的阵型
+#[derive(Debug, Default)]
+pub struct State {
+    cols: Vec<VecDeque<Unit>>,
+    drag: Option<(usize, usize)>, // 正在拖动的单位：(列, 行)
+}
+impl State {
+    pub fn load(&mut self, cols: Vec<VecDeque<Unit>>) {
+        self.cols = cols;
+        self.drag = None;
+    }
+
+    pub fn formation(&self) -> Formation {
+        Formation::from_army(&self.cols)
+    }
+
+    // 把单位移到 (列, 行)，列号等于列数时新建一列；返回阵型是否变化
+    fn move_unit(&mut self, from: (usize, usize), to: (usize, usize), max_cols: usize) -> bool {
+        let Some(unit) = self.cols.get_mut(from.0).and_then(|col| col.remove(from.1)) else {
+            return false;
+        };
+        if to.0 >= self.cols.len() && self.cols.len() < max_cols {
+            self.cols.push(VecDeque::new());
+        }
+        let col = to.0.min(self.cols.len() - 1);
+        // 同列向后移动时，移除后目标行前移一位
+        let row = if col == from.0 && to.1 > from.1 {
+            to.1 - 1
+        } else {
+            to.1
+        };
+        let column = &mut self.cols[col];
+        let row = row.min(column.len());
+        column.insert(row, unit);
+        self.cols.retain(|col| !col.is_empty());
+        (col, row) != from
+    }
+}
+
+pub fn render(ui: &mut Ui, state: &mut State, max_cols: usize) -> Option<Command> {
+    let rect = ui.available_rect_before_wrap();
+    let header = Rect::from_min_size(rect.min, vec2(rect.width(), ui.rem(1.0)));
+    let mut command = None;
+    let title = format!("{} {}/{}", tr("Formation"), state.cols.len(), max_cols);
+    if page_header::render(ui, header, &title) {
+        command = Some(Command::Pop);
+    }
+
+    // 未满时末尾多一列空位，放入即新建一列
+    let slots = if state.cols.len() < max_cols {
+        state.cols.len() + 1
+    } else {
+        state.cols.len()
+    }
+    .max(1);
+    let body = Rect::from_min_max(pos2(rect.min.x, header.max.y), rect.max);
+    let cell_width = (body.width() / slots as f32).max(ui.rem(1.0) * unit_ui::MIN_SCALE);
+    let cell = vec2(cell_width, ui.rem(1.0));
+    // 多一行用于放到列尾
+    let rows = state.cols.iter().map(VecDeque::len).max().unwrap_or(0) + 1;
+
+    let mut dropped = None;
+    ui.scope_builder(UiBuilder::new().max_rect(body), |ui| {
+        // 列数多时横向也可滚动
+        ScrollArea::both()
+            // 拖动用于移动单位，滚动只用滚动条和滚轮
+            .scroll_source(ScrollSource {
+                drag: false,
+                ..ScrollSource::ALL
+            })
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let (grid, _) = ui.allocate_exact_size(
+                    vec2(cell.x * slots as f32, rows as f32 * cell.y),
+                    Sense::hover(),
+                );
+                let cell_rect = |col: usize, row: usize| {
+                    Rect::from_min_size(
+                        grid.min + vec2(col as f32 * cell.x, row as f32 * cell.y),
+                        cell,
+                    )
+                };
+
+                // 列底框
+                for col in 0..slots {
+                    let col_rect = Rect::from_min_size(
+                        pos2(grid.min.x + col as f32 * cell.x, grid.min.y),
+                        vec2(cell.x, grid.height()),
+                    );
+                    ui.painter().rect_stroke(
+                        col_rect.shrink(ui.rem(0.02)),
+                        0.0,
+                        Stroke::new(
+                            ui.rem(0.02),
+                            ui.visuals().widgets.noninteractive.bg_stroke.color,
+                        ),
+                        StrokeKind::Inside,
+                    );
+                }
+
+                // 单位，拖动中的单位原位置留空
+                for (c, column) in state.cols.iter().enumerate() {
+                    for (r, unit) in column.iter().enumerate() {
+                        if state.drag == Some((c, r)) {
+                            continue;
+                        }
+                        let mut rect = cell_rect(c, r);
+                        unit_ui::render(ui, &mut rect, unit, ArmyType::Ally, &[]);
+                        let response =
+                            ui.interact(rect, Id::new(("formation", unit.id)), Sense::drag());
+                        if response.drag_started() && state.drag.is_none() {
+                            state.drag = Some((c, r));
+                        }
+                    }
+                }
+
+                let Some(from) = state.drag else {
+                    return;
+                };
+                let Some(pos) = ui.ctx().pointer_latest_pos() else {
+                    return;
+                };
+                // 落点：指针所在列，最近的行间隙
+                let col =
+                    (((pos.x - grid.min.x) / cell.x).floor().max(0.0) as usize).min(slots - 1);
+                let len = state.cols.get(col).map_or(0, VecDeque::len);
+                let row = (((pos.y - grid.min.y) / cell.y).round().max(0.0) as usize).min(len);
+                let target = cell_rect(col, row);
+                ui.painter().hline(
+                    target.x_range(),
+                    target.min.y,
+                    Stroke::new(ui.rem(0.04), Color32::from_rgb(60, 120, 220)),
+                );
+
+                // 跟随指针的单位
+                if let Some(unit) = state.cols.get(from.0).and_then(|col| col.get(from.1)) {
+                    Area::new(Id::new("formation_drag"))
+                        .order(Order::Tooltip)
+                        .interactable(false)
+                        .fixed_pos(pos - cell / 2.0)
+                        .show(ui.ctx(), |ui| {
+                            let mut rect = Rect::from_center_size(pos, cell);
+                            unit_ui::render(ui, &mut rect, unit, ArmyType::Ally, &[]);
+                        });
+                }
+
+                // 靠近上下边缘时自动滚动
+                let step = ui.rem(0.1);
+                if pos.y < body.min.y + cell.y {
+                    ui.scroll_with_delta(Vec2::new(0.0, step));
+                } else if pos.y > body.max.y - cell.y {
+                    ui.scroll_with_delta(Vec2::new(0.0, -step));
+                }
+
+                if !ui.input(|i| i.pointer.any_down()) {
+                    state.drag = None;
+                    dropped = Some((from, (col, row)));
+                }
+            });
+    });
+
+    if let Some((from, to)) = dropped
+        && state.move_unit(from, to, max_cols)
+    {
+        command = Some(Command::SaveFormation);
+    }
+    command
+}
@@ -1,41 +1,55 @@
 use std::collections::VecDeque;
 
-use egui::{Align2, Color32, FontId, Rect, Response, Sense, Ui, Vec2, pos2, vec2};
+use egui::{
+    Align2, Color32, FontId, Rect, ScrollArea, Sense, Stroke, StrokeKind, Ui, UiBuilder, Vec2,
+    pos2, vec2,
+};
 
 use crate::{
     UiExt,
-    components::unit_ui::{self, ArmyType},
-    core::batttle::{ArmySnapshot, BattleEvent},
-    model::Unit,
+    command::Command,
+    components::{
+        anim::AnimScheduler,
+        unit_ui::{self, ArmyType},
+    },
+    core::batttle::{ArmySnapshot, BattleReport, BattleSpeed},
+    i18n::tr,
+    model::{Faction, Unit},
 };
 
+// 战斗状态，决定中间按钮是否可用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BattleStatus {
+    Idle,     // 未开始
+    Running,  // 战斗中
+    Finished, // 已结束，显示结算
+}
+
 pub fn render(
     ui: &mut Ui,
     army: &ArmySnapshot,
-    events: &VecDeque<BattleEvent>,
-) -> (Response, Response, Response) {
+    anims: &AnimScheduler,
+    report: Option<&BattleReport>,
+    status: BattleStatus,
+    auto: bool,
+    speed: BattleSpeed,
+) -> Option<Command> {
     ui.spacing_mut().item_spacing = Vec2::ZERO;
     let rect = ui.available_rect_before_wrap();
-    let h = rect.height();
-    let _h = ui.rem(1.2); // 单元格高度
     let (top_rect, middle_rect, bottom_rect) = split_rect_vertically(rect, 0.42, 0.08);
 
-    let count1 = (h * 0.42 / _h) as usize + 1;
-    let count2 = (h * 0.50 / _h) as usize + 1;
     // 敌方区域
-    unit_grid_ui(ui, top_rect, &army.enemys, count1, ArmyType::Enemy, &events);
+    unit_grid_ui(ui, top_rect, &army.enemys, ArmyType::Enemy, anims);
     // 我方阵型
-    unit_grid_ui(
-        ui,
-        bottom_rect,
-        &army.allys,
-        count2,
-        ArmyType::Ally,
-        &events,
-    );
+    unit_grid_ui(ui, bottom_rect, &army.allys, ArmyType::Ally, anims);
 
     ui.allocate_rect(rect, Sense::hover()); // 手动分配占满
-    middle_ui(ui, middle_rect)
+    let command = middle_ui(ui, middle_rect, army, status, auto, speed);
+    // 结算
+    if let Some(report) = report {
+        result_ui(ui, top_rect, report);
+    }
+    command
 }
 
 // 渲染 单位网格
@@ -43,9 +57,8 @@ fn unit_grid_ui(
     ui: &mut Ui,
     rect: Rect,
     units2: &[VecDeque<Unit>],
-    max_count: usize,
     army_type: ArmyType,
-    events: &VecDeque<BattleEvent>,
+    anims: &AnimScheduler,
 ) {
     if units2.is_empty() {
         return;
@@ -53,93 +66,237 @@ fn unit_grid_ui(
     let unit_width = ui.rem(1.0);
     let unit_height = ui.rem(0.8);
     let num_cols = units2.len(); // 列数
-    let cell_width = (rect.width() / num_cols as f32).clamp(unit_width, 2.0 * unit_height);
-    let cell_height = 1.2 * unit_width;
-    let start_x = rect.min.x + (rect.width() - cell_width * num_cols as f32) / 2.0;
-
-    for (col_idx, column) in units2.iter().enumerate() {
-        let x = start_x + col_idx as f32 * cell_width;
-
-        // 直接渲染所有收到的单位（后端已裁剪）
-        for (row_idx, unit) in column.iter().take(max_count).enumerate() {
-            let mut unit_rect = match army_type {
-                ArmyType::Enemy => {
-                    let y_bottom = rect.max.y - row_idx as f32 * cell_height;
-                    Rect::from_min_max(
-                        pos2(x, y_bottom - cell_height),
-                        pos2(x + cell_width, y_bottom),
-                    )
-                }
-                ArmyType::Ally => {
-                    let y_top = rect.min.y + row_idx as f32 * cell_height;
-                    Rect::from_min_size(pos2(x, y_top), vec2(cell_width, cell_height))
-                }
-            };
-
-            let relevant_events: Vec<&BattleEvent> = events
-                .iter()
-                .filter(|ev| match ev {
-                    BattleEvent::ATK { id, .. } if *id == unit.id as u128 => true,
-                    BattleEvent::DEF { id, .. } if *id == unit.id as u128 => true,
-                    _ => false,
-                })
-                .collect();
-
-            unit_ui::render(ui, &mut unit_rect, unit, army_type, &relevant_events);
+    let cell_width =
+        (rect.width() / num_cols as f32).clamp(unit_width * unit_ui::MIN_SCALE, 2.0 * unit_height);
+    let cell_height = 1.2 * cell_width.min(unit_width);
+    let max_count = (rect.height() / cell_height) as usize + 1; // 可见行数
+    let content_width = cell_width * num_cols as f32;
+
+    let cells = |ui: &mut Ui, start_x: f32| {
+        for (col_idx, column) in units2.iter().enumerate() {
+            let x = start_x + col_idx as f32 * cell_width;
+
+            // 直接渲染所有收到的单位（后端已裁剪）
+            for (row_idx, unit) in column.iter().take(max_count).enumerate() {
+                let mut unit_rect = match army_type {
+                    ArmyType::Enemy => {
+                        let y_bottom = rect.max.y - row_idx as f32 * cell_height;
+                        Rect::from_min_max(
+                            pos2(x, y_bottom - cell_height),
+                            pos2(x + cell_width, y_bottom),
+                        )
+                    }
+                    ArmyType::Ally => {
+                        let y_top = rect.min.y + row_idx as f32 * cell_height;
+                        Rect::from_min_size(pos2(x, y_top), vec2(cell_width, cell_height))
+                    }
+                };
+
+                unit_ui::render(ui, &mut unit_rect, unit, army_type, anims.get(unit.id));
+            }
         }
+    };
+
+    if content_width <= rect.width() {
+        cells(ui, rect.min.x + (rect.width() - content_width) / 2.0);
+        return;
     }
+    // 缩到最小仍放不下：横向滚动
+    ui.scope_builder(UiBuilder::new().max_rect(rect), |ui| {
+        ScrollArea::horizontal()
+            .id_salt(army_type)
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let (grid, _) =
+                    ui.allocate_exact_size(vec2(content_width, rect.height()), Sense::hover());
+                cells(ui, grid.min.x);
+            });
+    });
 }
 
-fn middle_ui(ui: &mut Ui, rect: Rect) -> (Response, Response, Response) {
+// 中间栏：自动战斗（run）、开始（start）、返回上一页（back），右侧为速度
+fn middle_ui(
+    ui: &mut Ui,
+    rect: Rect,
+    army: &ArmySnapshot,
+    status: BattleStatus,
+    auto: bool,
+    speed: BattleSpeed,
+) -> Option<Command> {
+    hp_bar_ui(ui, rect, army);
     let rect = rect.shrink(ui.rem(0.1));
     let count_font = FontId::proportional(ui.rem(0.3));
     let run_font = FontId::proportional(ui.rem(0.56));
     let start_font = FontId::proportional(ui.rem(0.7));
     let back_font = FontId::proportional(ui.rem(0.46));
+    let text_color = ui.visuals().strong_text_color();
 
     let p = ui.painter();
     p.text(
         rect.left_top(),
         Align2::LEFT_TOP,
-        "\u{E6A6} 99",
+        format!("\u{E6A6} {}", army.enemys_num),
         count_font.clone(),
-        Color32::BLACK,
+        text_color,
     );
     p.text(
         rect.left_bottom(),
         Align2::LEFT_BOTTOM,
-        "\u{E62A} 99",
+        format!("\u{E62A} {}", army.allys_num),
         count_font.clone(),
-        Color32::BLACK,
+        text_color,
     );
 
     let (left_rect, mid_rect, right_rect) = compute_three_rects(rect, ui.rem(1.2));
-    p.text(
-        left_rect.center(),
-        Align2::CENTER_CENTER,
-        "\u{E64C}",
-        run_font,
-        Color32::BLACK,
+    let running = status == BattleStatus::Running;
+    let mut command = None;
+    if button(ui, left_rect, "\u{E64C}", run_font, true, auto) {
+        command = Some(Command::ToggleAuto);
+    }
+    if button(ui, mid_rect, "\u{E65C}", start_font, !running, false) {
+        command = Some(Command::StartBattle);
+    }
+    if button(ui, right_rect, "\u{E68D}", back_font, true, false) {
+        command = Some(Command::Pop);
+    }
+    let speed_rect = ui.painter().text(
+        rect.right_center(),
+        Align2::RIGHT_CENTER,
+        tr(speed.label()),
+        count_font,
+        text_color,
     );
-    p.text(
-        mid_rect.center(),
-        Align2::CENTER_CENTER,
-        "\u{E65C}",
-        start_font,
-        Color32::BLACK,
+    if ui
+        .allocate_rect(speed_rect.expand(ui.rem(0.1)), Sense::click())
+        .clicked()
+    {
+        command = Some(Command::CycleSpeed);
+    }
+    command
+}
+
+// 拔河血条：我方（蓝）从左、敌方（红）从右，按双方剩余总生命分割
+fn hp_bar_ui(ui: &Ui, rect: Rect, army: &ArmySnapshot) {
+    if army.allys_num + army.enemys_num == 0 {
+        return;
+    }
+    let bar = Rect::from_min_size(rect.min, vec2(rect.width(), ui.rem(0.06)));
+    let split = bar.min.x + bar.width() * army.ally_hp_ratio();
+    let p = ui.painter();
+    p.rect_filled(
+        Rect::from_min_max(bar.min, pos2(split, bar.max.y)),
+        0.0,
+        Color32::from_rgb(60, 120, 220),
+    );
+    p.rect_filled(
+        Rect::from_min_max(pos2(split, bar.min.y), bar.max),
+        0.0,
+        Color32::from_rgb(220, 40, 40),
+    );
+}
+
+// 图标按钮：禁用时灰色且不响应点击，激活时高亮
+fn button(ui: &mut Ui, rect: Rect, icon: &str, font: FontId, enabled: bool, active: bool) -> bool {
+    let color = match (enabled, active) {
+        (false, _) => ui.visuals().weak_text_color(),
+        (true, true) => Color32::from_rgb(220, 40, 40),
+        (true, false) => ui.visuals().strong_text_color(),
+    };
+    ui.painter()
+        .text(rect.center(), Align2::CENTER_CENTER, icon, font, color);
+    let sense = if enabled {
+        Sense::click()
+    } else {
+        Sense::hover()
+    };
+    ui.allocate_rect(rect, sense).clicked()
+}
+
+// 渲染 战斗结算
+fn result_ui(ui: &mut Ui, rect: Rect, report: &BattleReport) {
+    let rect = rect.shrink(ui.rem(0.3));
+    let title_font = FontId::proportional(ui.rem(0.5));
+    let text_font = FontId::proportional(ui.rem(0.24));
+    let line_h = ui.rem(0.36);
+
+    let p = ui.painter();
+    p.rect_filled(rect, ui.rem(0.1), Color32::from_white_alpha(235));
+    p.rect_stroke(
+        rect,
+        ui.rem(0.1),
+        Stroke::new(ui.rem(0.03), Color32::BLACK),
+        StrokeKind::Inside,
     );
+
+    let (title, color) = match report.winner {
+        Some(Faction::Ally) => (tr("VICTORY"), Color32::from_rgb(220, 40, 40)),
+        Some(Faction::Enemy) => (tr("DEFEAT"), Color32::DARK_GRAY),
+        None => (tr("DRAW"), Color32::DARK_GRAY),
+    };
+    let mut y = rect.min.y + ui.rem(0.2);
     p.text(
-        right_rect.center(),
-        Align2::CENTER_CENTER,
-        "\u{E68D}",
-        back_font,
-        Color32::BLACK,
+        pos2(rect.center().x, y),
+        Align2::CENTER_TOP,
+        title,
+        title_font,
+        color,
     );
-    (
-        ui.allocate_rect(left_rect, Sense::click()),
-        ui.allocate_rect(mid_rect, Sense::click()),
-        ui.allocate_rect(right_rect, Sense::click()),
-    )
+    y += ui.rem(0.8);
+
+    let mut lines = vec![
+        format!(
+            "{} {:.1}s    {} {}",
+            tr("Time"),
+            report.duration_ms as f32 / 1000.0,
+            tr("Seed"),
+            report.seed
+        ),
+        format!(
+            "{}  {} {}  {} {}",
+            tr("Alive"),
+            tr("Ally"),
+            report.allys_survivors.len(),
+            tr("Enemy"),
+            report.enemys_survivors.len()
+        ),
+        tr("Top damage").to_string(),
+    ];
+    // 我方输出前五
+    let mut top: Vec<_> = report
+        .units
+        .iter()
+        .filter(|s| s.faction == Faction::Ally)
+        .collect();
+    top.sort_by_key(|s| std::cmp::Reverse(s.damage_dealt));
+    lines.extend(top.iter().take(5).map(|s| {
+        format!(
+            "{}#{}  {} {}  {} {}  {} {}  {} {}",
+            s.name,
+            s.id,
+            tr("DMG"),
+            s.damage_dealt,
+            tr("TAKEN"),
+            s.damage_taken,
+            tr("KILL"),
+            s.kills,
+            tr("ATK"),
+            s.attacks
+        )
+    }));
+
+    for line in lines {
+        if y + line_h > rect.max.y {
+            break;
+        }
+        p.text(
+            pos2(rect.min.x + ui.rem(0.2), y),
+            Align2::LEFT_TOP,
+            line,
+            text_font.clone(),
+            Color32::BLACK,
+        );
+        y += line_h;
+    }
 }
 
 // 切分矩形
@@ -0,0 +1,81 @@
+// 首页：进入各个页面
+
+use egui::{Align2, FontId, Rect, Sense, Stroke, StrokeKind, Ui, pos2, vec2};
+
+use crate::{UiExt, command::Command, i18n::tr, router::Route, save::Progress};
+
+// 首页入口：显示名称 -> 路由
+const ENTRIES: [(&str, Route); 4] = [
+    ("Battle", Route::Battle),
+    ("Formation", Route::Army),
+    ("Resources", Route::Resource),
+    ("Settings", Route::Settings),
+];
+
+pub fn render(ui: &mut Ui, progress: &Progress) -> Option<Command> {
+    let rect = ui.available_rect_before_wrap();
+    ui.allocate_rect(rect, Sense::hover());
+
+    let title_font = FontId::proportional(ui.rem(0.6));
+    let font = FontId::proportional(ui.rem(0.36));
+    let visuals = ui.visuals();
+    let p = ui.painter();
+    p.text(
+        pos2(rect.center().x, rect.min.y + rect.height() * 0.2),
+        Align2::CENTER_CENTER,
+        tr("DQWM"),
+        title_font,
+        visuals.strong_text_color(),
+    );
+    p.text(
+        pos2(
+            rect.center().x,
+            rect.min.y + rect.height() * 0.2 + ui.rem(0.6),
+        ),
+        Align2::CENTER_CENTER,
+        format!(
+            "{} {}  {} {}",
+            tr("Won"),
+            progress.battles_won,
+            tr("Lost"),
+            progress.battles_lost
+        ),
+        font.clone(),
+        visuals.weak_text_color(),
+    );
+
+    let size = vec2(ui.rem(3.0), ui.rem(0.8));
+    let gap = ui.rem(0.3);
+    let mut top = rect.min.y + rect.height() * 0.4;
+    let mut command = None;
+    for (label, route) in ENTRIES {
+        let button_rect = Rect::from_min_size(pos2(rect.center().x - size.x / 2.0, top), size);
+        if menu_button(ui, button_rect, tr(label), font.clone()) {
+            command = Some(Command::Push(route));
+        }
+        top += size.y + gap;
+    }
+    command
+}
+
+// 文字按钮：按下时反色
+fn menu_button(ui: &mut Ui, rect: Rect, label: &str, font: FontId) -> bool {
+    let response = ui.allocate_rect(rect, Sense::click());
+    let text = ui.visuals().strong_text_color();
+    let background = ui.visuals().extreme_bg_color;
+    let (bg, fg) = if response.is_pointer_button_down_on() {
+        (text, background)
+    } else {
+        (background, text)
+    };
+    let p = ui.painter();
+    p.rect_filled(rect, 0.0, bg);
+    p.rect_stroke(
+        rect,
+        0.0,
+        Stroke::new(ui.rem(0.03), text),
+        StrokeKind::Inside,
+    );
+    p.text(rect.center(), Align2::CENTER_CENTER, label, font, fg);
+    response.clicked()
+}
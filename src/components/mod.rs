@@ -1,3 +1,9 @@
+pub mod anim;
+pub mod army_page;
 pub mod battle_page;
+pub mod home_page;
+pub mod page_header;
+pub mod resource_page;
+pub mod settings_page;
 pub mod unit_card;
 pub mod unit_ui;
@@ -0,0 +1,28 @@
+// 页面标题栏：左侧返回，中间标题，返回是否点击了返回
+
+use egui::{Align2, FontId, Rect, Sense, Ui, vec2};
+
+use crate::UiExt;
+
+pub fn render(ui: &mut Ui, rect: Rect, title: &str) -> bool {
+    let font = FontId::proportional(ui.rem(0.44));
+    let pad = ui.rem(0.3);
+    let color = ui.visuals().strong_text_color();
+    let p = ui.painter();
+    p.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        title,
+        font.clone(),
+        color,
+    );
+    let back = p.text(
+        rect.left_center() + vec2(pad, 0.0),
+        Align2::LEFT_CENTER,
+        "\u{E68D}",
+        font,
+        color,
+    );
+    ui.allocate_rect(back.expand(pad / 2.0), Sense::click())
+        .clicked()
+}
@@ -0,0 +1,89 @@
+// 资源页：列出全部资源，点选后显示产出构成
+
+use std::collections::BTreeMap;
+
+use egui::{Align2, Color32, FontId, Id, Rect, Sense, Ui, pos2, vec2};
+
+use crate::{
+    UiExt,
+    command::Command,
+    components::page_header,
+    core::{
+        bonus::{BonusManager, BonusTarget},
+        resource::DQWMResource,
+    },
+    i18n::tr,
+};
+
+// 页面状态
+#[derive(Debug, Default)]
+pub struct State {
+    selected: Option<u32>, // 选中的资源 id
+}
+
+pub fn render(
+    ui: &mut Ui,
+    state: &mut State,
+    resources: &BTreeMap<u32, DQWMResource>,
+    bonuses: &BonusManager,
+) -> Option<Command> {
+    let rect = ui.available_rect_before_wrap();
+    ui.allocate_rect(rect, Sense::hover());
+
+    let font = FontId::proportional(ui.rem(0.3));
+    let small_font = FontId::proportional(ui.rem(0.22));
+    let pad = ui.rem(0.3);
+    let row_h = ui.rem(0.9);
+
+    let header = Rect::from_min_size(rect.min, vec2(rect.width(), ui.rem(1.0)));
+    let mut command = None;
+    if page_header::render(ui, header, tr("Resources")) {
+        command = Some(Command::Pop);
+    }
+
+    let mut top = header.max.y;
+    for r in resources.values() {
+        let row = Rect::from_min_size(pos2(rect.min.x, top), vec2(rect.width(), row_h));
+        if ui
+            .interact(row, Id::new(("resource_row", r.id)), Sense::click())
+            .clicked()
+        {
+            state.selected = (state.selected != Some(r.id)).then_some(r.id);
+        }
+        let selected = state.selected == Some(r.id);
+        let p = ui.painter();
+        if selected {
+            p.rect_filled(row, 0.0, ui.visuals().widgets.inactive.bg_fill);
+        }
+        p.text(
+            row.left_center() + vec2(pad, 0.0),
+            Align2::LEFT_CENTER,
+            format!("{}  {}/{}", r.name, r.value, r.max),
+            font.clone(),
+            ui.visuals().strong_text_color(),
+        );
+        p.text(
+            row.right_center() - vec2(pad, 0.0),
+            Align2::RIGHT_CENTER,
+            format!("+{}/{}s", r.change, r.interval as f32 / 1000.0),
+            font.clone(),
+            Color32::from_rgb(40, 160, 60),
+        );
+        top = row.max.y;
+
+        // 展开：描述 + 加成构成
+        if selected {
+            let breakdown =
+                bonuses.breakdown(BonusTarget::ResourceChange(r.id), r.base_change as f64);
+            let detail = p.text(
+                pos2(rect.min.x + pad, top),
+                Align2::LEFT_TOP,
+                format!("{}\n{}", r.description, breakdown),
+                small_font.clone(),
+                ui.visuals().text_color(),
+            );
+            top = detail.max.y + pad / 2.0;
+        }
+    }
+    command
+}
@@ -0,0 +1,173 @@
+// 设置页：每项修改立即生效并保存
+
+use egui::{Align2, FontId, Rect, Sense, Ui, pos2, vec2};
+
+use crate::{
+    UiExt,
+    command::Command,
+    components::page_header,
+    i18n::tr,
+    settings::{MAX_COLS, Settings},
+};
+
+// 设置项
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    EnemyCols,
+    AllyCols,
+    ShowFps,
+    BattleSpeed,
+    AnimQuality,
+    Language,
+    Theme,
+}
+const FIELDS: [Field; 7] = [
+    Field::EnemyCols,
+    Field::AllyCols,
+    Field::ShowFps,
+    Field::BattleSpeed,
+    Field::AnimQuality,
+    Field::Language,
+    Field::Theme,
+];
+
+// 设置项的操作
+enum Control {
+    Cycle(&'static str), // 点击切换到下一个值
+    Step(usize),         // 数值，两侧减、加，范围 1..=MAX_COLS
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::EnemyCols => "Enemy columns",
+            Field::AllyCols => "Ally columns",
+            Field::ShowFps => "Debug overlay",
+            Field::BattleSpeed => "Battle speed",
+            Field::AnimQuality => "Animations",
+            Field::Language => "Language",
+            Field::Theme => "Theme",
+        }
+    }
+
+    fn control(self, s: &Settings) -> Control {
+        match self {
+            Field::EnemyCols => Control::Step(s.max_enemy_cols),
+            Field::AllyCols => Control::Step(s.max_ally_cols),
+            Field::ShowFps => Control::Cycle(if s.show_fps { "On" } else { "Off" }),
+            Field::BattleSpeed => Control::Cycle(s.battle_speed.label()),
+            Field::AnimQuality => Control::Cycle(s.anim_quality.label()),
+            Field::Language => Control::Cycle(s.language.label()),
+            Field::Theme => Control::Cycle(s.theme.label()),
+        }
+    }
+
+    // 切换项 delta 恒为 1，数值项为 ±1
+    fn change(self, mut s: Settings, delta: isize) -> Settings {
+        let step = |v: usize| v.saturating_add_signed(delta).clamp(1, MAX_COLS);
+        match self {
+            Field::EnemyCols => s.max_enemy_cols = step(s.max_enemy_cols),
+            Field::AllyCols => s.max_ally_cols = step(s.max_ally_cols),
+            Field::ShowFps => s.show_fps = !s.show_fps,
+            Field::BattleSpeed => s.battle_speed = s.battle_speed.next(),
+            Field::AnimQuality => s.anim_quality = s.anim_quality.next(),
+            Field::Language => s.language = s.language.next(),
+            Field::Theme => s.theme = s.theme.next(),
+        }
+        s
+    }
+}
+
+pub fn render(ui: &mut Ui, settings: &Settings) -> Option<Command> {
+    let rect = ui.available_rect_before_wrap();
+    ui.allocate_rect(rect, Sense::hover());
+    let header = Rect::from_min_size(rect.min, vec2(rect.width(), ui.rem(1.0)));
+    let mut command = None;
+    if page_header::render(ui, header, tr("Settings")) {
+        command = Some(Command::Pop);
+    }
+
+    let font = FontId::proportional(ui.rem(0.3));
+    let color = ui.visuals().strong_text_color();
+    let pad = ui.rem(0.3);
+    let row_h = ui.rem(0.9);
+    let mut top = header.max.y;
+    for field in FIELDS {
+        let row = Rect::from_min_size(pos2(rect.min.x, top), vec2(rect.width(), row_h));
+        top = row.max.y;
+        ui.painter().text(
+            row.left_center() + vec2(pad, 0.0),
+            Align2::LEFT_CENTER,
+            tr(field.label()),
+            font.clone(),
+            color,
+        );
+        // 值在右侧
+        let delta: isize = match field.control(settings) {
+            Control::Cycle(value) => {
+                let value_rect = ui.painter().text(
+                    row.right_center() - vec2(pad, 0.0),
+                    Align2::RIGHT_CENTER,
+                    tr(value),
+                    font.clone(),
+                    ui.visuals().hyperlink_color,
+                );
+                let clicked = ui
+                    .allocate_rect(value_rect.expand(pad / 2.0), Sense::click())
+                    .clicked();
+                isize::from(clicked)
+            }
+            Control::Step(value) => {
+                let p = ui.painter();
+                let plus = p.text(
+                    row.right_center() - vec2(pad, 0.0),
+                    Align2::RIGHT_CENTER,
+                    "+",
+                    font.clone(),
+                    if value < MAX_COLS {
+                        color
+                    } else {
+                        ui.visuals().weak_text_color()
+                    },
+                );
+                let value_rect = p.text(
+                    plus.left_center() - vec2(pad, 0.0),
+                    Align2::RIGHT_CENTER,
+                    value.to_string(),
+                    font.clone(),
+                    color,
+                );
+                let minus = p.text(
+                    value_rect.left_center() - vec2(pad, 0.0),
+                    Align2::RIGHT_CENTER,
+                    "-",
+                    font.clone(),
+                    if value > 1 {
+                        color
+                    } else {
+                        ui.visuals().weak_text_color()
+                    },
+                );
+                if ui
+                    .allocate_rect(plus.expand(pad / 2.0), Sense::click())
+                    .clicked()
+                    && value < MAX_COLS
+                {
+                    1
+                } else if ui
+                    .allocate_rect(minus.expand(pad / 2.0), Sense::click())
+                    .clicked()
+                    && value > 1
+                {
+                    -1
+                } else {
+                    0
+                }
+            }
+        };
+        if delta != 0 {
+            command = Some(Command::UpdateSettings(field.change(*settings, delta)));
+        }
+    }
+    command
+}
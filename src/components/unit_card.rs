@@ -1,6 +1,9 @@
 use std::time::{Duration, Instant};
 
-use crate::{R, UiExt, model::Unit};
+use crate::{
+    R, UiExt,
+    model::{Unit, UnitId},
+};
 
 use egui::{
     Color32, FontId, Id, Layout, Rect, Response, RichText, Sense, Stroke, StrokeKind, Ui,
@@ -73,7 +76,7 @@ fn _bg_hp(ui: &mut Ui, rect: Rect, ratio: f32, opacity: f32) {
         .rect_filled(bg_rect, 0.0, with_alpha(Color32::RED, opacity));
 }
 
-fn _anim_atk(ui: &mut Ui, rect: &mut Rect, id: usize, time: Option<Instant>, army_type: ArmyType) {
+fn _anim_atk(ui: &mut Ui, rect: &mut Rect, id: UnitId, time: Option<Instant>, army_type: ArmyType) {
     const DURATION: Duration = Duration::from_millis(400);
     let ctx = ui.ctx();
     let height = rect.height();
@@ -117,7 +120,7 @@ fn _anim_atk(ui: &mut Ui, rect: &mut Rect, id: usize, time: Option<Instant>, arm
     rect.max.y += offset_y;
 }
 
-fn _anim_def(ui: &mut Ui, id: usize, trigger: Option<Instant>) -> f32 {
+fn _anim_def(ui: &mut Ui, id: UnitId, trigger: Option<Instant>) -> f32 {
     const DURATION: Duration = Duration::from_millis(300);
     let ctx = ui.ctx();
     let anim_id = Id::new(("DEF", id));
@@ -152,7 +155,7 @@ fn _draw_damage_text(
     ui: &mut Ui,
     rect: Rect,
     trigger: Option<Instant>,
-    id: usize,
+    id: UnitId,
     army_type: ArmyType,
 ) {
     const TEXT: &str = "-9999";
@@ -244,7 +247,7 @@ pub fn render(ui: &mut Ui, unit: &Unit) -> Response {
         .size(Size::relative(0.240))
         .vertical(|mut strip| {
             strip.empty();
-            strip.cell(|ui| _build_name(ui, unit.name));
+            strip.cell(|ui| _build_name(ui, &unit.name));
             strip.cell(|ui| _build_hp(ui, unit.hp, unit.max_hp));
             strip.empty();
             strip.cell(|ui| {
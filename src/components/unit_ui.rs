@@ -1,13 +1,17 @@
 // Unit渲染方法
 
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use egui::{
-    Align2, Color32, FontId, Id, Rect, Response, Sense, Shadow, Stroke, StrokeKind, Ui, hex_color,
+    Align2, Color32, FontId, Rect, Response, Sense, Shadow, Stroke, StrokeKind, Ui, hex_color,
     pos2, vec2,
 };
 
-use crate::{UiExt, core::batttle::BattleEvent, model::Unit};
+use crate::{
+    UiExt,
+    components::anim::{Anim, AnimKind, Popup},
+    model::Unit,
+};
 
 // 宏用于计算累计高度
 macro_rules! sum_arr {
@@ -16,7 +20,10 @@ macro_rules! sum_arr {
     };
 }
 
-#[derive(Debug, Clone, Copy)]
+// 列数多时卡片最多缩小到的比例，仍放不下时由网格横向滚动
+pub const MIN_SCALE: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ArmyType {
     Ally,
     Enemy,
@@ -27,35 +34,34 @@ pub fn render(
     cell_rect: &mut Rect,
     unit: &Unit,
     army_type: ArmyType,
-    events: &[&BattleEvent],
+    anims: &[Anim],
 ) -> Response {
-    let w = ui.rem(1.0);
-    let h = ui.rem(0.8);
-    let border_width = ui.rem(0.03);
-    let shadow_blur = ui.rem(0.16) as u8;
-    let name_font = FontId::proportional(ui.rem(0.20));
-    let hp_font = FontId::proportional(ui.rem(0.16));
-    let attr_font = FontId::proportional(ui.rem(0.16));
+    // 单元格比卡片窄时按比例缩小，避免相邻卡片重叠
+    let scale = (cell_rect.width() / ui.rem(1.0)).min(1.0);
+    let rem = |v: f32| ui.rem(v) * scale;
+    let w = rem(1.0);
+    let h = rem(0.8);
+    let border_width = rem(0.03);
+    let shadow_blur = rem(0.16) as u8;
+    let name_font = FontId::proportional(rem(0.20));
+    let hp_font = FontId::proportional(rem(0.16));
+    let attr_font = FontId::proportional(rem(0.16));
 
     let mut rect = Rect::from_center_size(cell_rect.center(), egui::vec2(w, h));
     let response = ui.allocate_rect(rect, Sense::click());
 
     // let (mut rect, response) = ui.allocate_exact_size(vec2(w, h), Sense::click());
 
-    // 动画
-
+    // 动画：由 AnimScheduler 统一调度，这里只按进度计算
+    let now = Instant::now();
+    let base_rect = rect;
     let mut opacity = 1.0;
-    for e in events {
-        match e {
-            BattleEvent::ATK { timestamp, .. } => {
-                anim_atk(ui, &mut rect, unit.id, Some(*timestamp), army_type);
-            }
-            BattleEvent::DEF {
-                amount, timestamp, ..
-            } => {
-                opacity = anim_def(ui, unit.id, Some(*timestamp));
-                anim_text(ui, rect, Some(*timestamp), unit.id, army_type, *amount);
-            }
+    for anim in anims {
+        let t = anim.progress(now);
+        match &anim.kind {
+            AnimKind::Atk => rect = rect.translate(vec2(0.0, atk_offset(t, h, army_type))),
+            AnimKind::Hit => opacity = f32::min(opacity, t),
+            AnimKind::Popup(_) => {}
         }
     }
 
@@ -71,7 +77,7 @@ pub fn render(
 
     // 2、背景
     p.rect_filled(rect, 0.0, Color32::WHITE);
-    let hp_ratio = (unit.hp / unit.max_hp) as f32;
+    let hp_ratio = (unit.hp as f64 / unit.max_hp.max(1) as f64) as f32;
     p.rect_filled(
         Rect::from_min_size(rect.min, vec2(w * hp_ratio, h)),
         0.0,
@@ -112,7 +118,7 @@ pub fn render(
     p.text(
         name_rect.center_top(),
         Align2::CENTER_TOP,
-        unit.name,
+        &*unit.name,
         name_font,
         Color32::BLACK,
     );
@@ -144,153 +150,63 @@ pub fn render(
         Color32::WHITE,
     );
 
+    // 7、飘字，画在卡片上层
+    for anim in anims {
+        if let AnimKind::Popup(popup) = &anim.kind {
+            popup_ui(ui, base_rect, popup, anim.progress(now), army_type);
+        }
+    }
+
     response
 }
 
-fn anim_atk(ui: &Ui, rect: &mut Rect, id: usize, time: Option<Instant>, army_type: ArmyType) {
-    const DURATION: Duration = Duration::from_millis(400);
-    let ctx = ui.ctx();
-    let height = rect.height();
-    let now = Instant::now();
-    let anim_id = Id::new(("ATK", id));
+// 出手：先冲向对方再退回，返回垂直偏移
+fn atk_offset(t: f32, height: f32, army_type: ArmyType) -> f32 {
     let dir = match army_type {
         ArmyType::Ally => -1.0,
         ArmyType::Enemy => 1.0,
     };
-    let (offset_y, needs_repaint) = ctx.memory_mut(|mem| {
-        if let Some(start_time) = time {
-            mem.data.insert_temp(anim_id, start_time);
-        }
-        let Some(start_time) = mem.data.get_temp::<Instant>(anim_id) else {
-            return (0.0, false);
-        };
-        let elapsed = now.duration_since(start_time);
-        if elapsed >= DURATION {
-            mem.data.remove::<Instant>(anim_id);
-            return (0.0, false);
-        }
-
-        let t = elapsed.as_secs_f32() / DURATION.as_secs_f32();
-        let seg_t = if t < 0.5 { t * 2.0 } else { (t - 0.5) * 2.0 };
-        let max_off = 0.6 * height;
+    let seg_t = if t < 0.5 { t * 2.0 } else { (t - 0.5) * 2.0 };
+    let max_off = 0.6 * height;
 
-        let mag = if seg_t <= 0.5 {
-            (1.0 - (1.0 - seg_t * 2.0).powi(3)) * max_off
-        } else {
-            ((1.0 - (seg_t - 0.5) * 2.0).powi(3)) * max_off
-        };
-
-        (dir * mag, true)
-    });
-
-    if needs_repaint {
-        ctx.request_repaint();
-    }
-
-    rect.min.y += offset_y;
-    rect.max.y += offset_y;
-}
-
-fn anim_def(ui: &Ui, id: usize, trigger: Option<Instant>) -> f32 {
-    const DURATION: Duration = Duration::from_millis(300);
-    let ctx = ui.ctx();
-    let anim_id = Id::new(("DEF", id));
-
-    let (opacity, needs_repaint) = ctx.memory_mut(|mem| {
-        if let Some(now) = trigger {
-            mem.data.insert_temp(anim_id, now);
-        }
-
-        let Some(start_time) = mem.data.get_temp::<Instant>(anim_id) else {
-            return (1.0, false);
-        };
-
-        let elapsed = start_time.elapsed();
-        if elapsed >= DURATION {
-            mem.data.remove::<Instant>(anim_id);
-            return (1.0, false);
-        }
-
-        let opacity = (elapsed.as_secs_f32() / DURATION.as_secs_f32()).clamp(0.0, 1.0);
-        (opacity, true) // 需要重绘
-    });
-
-    if needs_repaint {
-        ctx.request_repaint(); // ← 安全：在闭包外调用
-    }
-
-    opacity
+    let mag = if seg_t <= 0.5 {
+        (1.0 - (1.0 - seg_t * 2.0).powi(3)) * max_off
+    } else {
+        ((1.0 - (seg_t - 0.5) * 2.0).powi(3)) * max_off
+    };
+    dir * mag
 }
 
-fn anim_text(
-    ui: &Ui,
-    rect: Rect,
-    trigger: Option<Instant>,
-    id: usize,
-    army_type: ArmyType,
-    num: u128,
-) {
-    let text: &str = &num.to_string();
-    const DURATION: Duration = Duration::from_millis(1000);
-    let ctx = ui.ctx();
-    let anim_id = Id::new(("DAMAGE_POPUP", id));
-
+fn popup_ui(ui: &Ui, base_rect: Rect, popup: &Popup, t: f32, army_type: ArmyType) {
     // 判断左右（用于水平飘动方向）
-    let screen_center_x = ctx.viewport_rect().center().x;
-    let is_on_left = rect.center().x < screen_center_x;
-
-    // === 状态更新（纯数据）===
-    let state = ctx.memory_mut(|mem| {
-        if let Some(start_time) = trigger {
-            mem.data.insert_temp(anim_id, (start_time, rect));
-        }
-
-        let (start_time, base_rect) = mem.data.get_temp::<(Instant, Rect)>(anim_id)?;
-
-        let elapsed = start_time.elapsed();
-        if elapsed >= DURATION {
-            mem.data.remove::<(Instant, Rect)>(anim_id);
-            return None;
-        }
-
-        Some((start_time, base_rect))
-    });
-
-    // === 绘制（闭包外）===
-    if let Some((start_time, base_rect)) = state {
-        let elapsed = start_time.elapsed();
-        let t = (elapsed.as_secs_f32() / DURATION.as_secs_f32()).clamp(0.0, 1.0);
-
-        // 垂直偏移：根据 army_type 决定向上 or 向下
-        let y_offset = match army_type {
-            ArmyType::Ally => -40.0 * t, // 上方冒出，向上飘
-            ArmyType::Enemy => 40.0 * t, // 下方冒出，向下飘
-        };
-
-        // 水平偏移：左→右，右→左
-        let x_offset = if is_on_left { 30.0 } else { -30.0 } * t;
-
-        // 起始锚点：Ally 用 top，Enemy 用 bottom
-        let anchor_y = match army_type {
-            ArmyType::Ally => base_rect.top(),
-            ArmyType::Enemy => base_rect.bottom(),
-        };
+    let screen_center_x = ui.ctx().viewport_rect().center().x;
+    let is_on_left = base_rect.center().x < screen_center_x;
+
+    // 垂直偏移：根据 army_type 决定向上 or 向下，同时出现的飘字按 slot 错开
+    let stagger = popup.slot as f32 * popup.size;
+    let y_offset = match army_type {
+        ArmyType::Ally => -40.0 * t - stagger, // 上方冒出，向上飘
+        ArmyType::Enemy => 40.0 * t + stagger, // 下方冒出，向下飘
+    };
 
-        let pos = pos2(base_rect.center().x + x_offset, anchor_y + y_offset);
+    // 水平偏移：左→右，右→左
+    let x_offset = if is_on_left { 30.0 } else { -30.0 } * t;
 
-        let alpha = ((1.0 - t) * 255.0).clamp(0.0, 255.0) as u8;
-        let color = Color32::from_rgba_unmultiplied(255, 50, 50, alpha); // 红色
+    // 起始锚点：Ally 用 top，Enemy 用 bottom
+    let anchor_y = match army_type {
+        ArmyType::Ally => base_rect.top(),
+        ArmyType::Enemy => base_rect.bottom(),
+    };
 
-        ui.painter().text(
-            pos,
-            egui::Align2::CENTER_CENTER,
-            text,
-            FontId::monospace(14.0),
-            color,
-        );
+    let pos = pos2(base_rect.center().x + x_offset, anchor_y + y_offset);
 
-        ctx.request_repaint();
-    }
+    ui.painter().text(
+        pos,
+        egui::Align2::CENTER_CENTER,
+        &popup.text,
+        FontId::monospace(popup.size),
+        with_alpha(popup.color, 1.0 - t),
+    );
 }
 
 #[inline]
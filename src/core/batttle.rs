@@ -1,52 +1,284 @@
-use std::{collections::VecDeque, time::Instant};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
 
-use flume::Sender;
+use flume::{Receiver, Sender, TryRecvError};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 
-use crate::model::Unit;
-#[derive(Debug, Default)]
-struct Army {
+use crate::{
+    core::{delivery::Outbox, rng::BattleRng},
+    global::{CONFIG, global_tokio_runtime},
+    model::{self, Faction, Unit, UnitId},
+};
+#[derive(Debug, Default, Clone)]
+pub struct Army {
     pub enemys: Vec<VecDeque<Unit>>,
     pub allys: Vec<VecDeque<Unit>>,
 }
 
+impl Army {
+    fn cols_mut(&mut self, faction: Faction) -> &mut Vec<VecDeque<Unit>> {
+        match faction {
+            Faction::Ally => &mut self.allys,
+            Faction::Enemy => &mut self.enemys,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum BattleOutput {
-    ArmySnapshot(ArmySnapshot),
+    ArmySnapshot(ArmySnapshot), // 开局及重新同步时发送全量
+    ArmyDiff(ArmyDiff),         // 之后每个时刻只发送变化
     BattleEvent(BattleEvent),
-    // 可扩展：Log(String), Progress(f32), Error(String), Done 等
+    Finished(BattleReport), // 战斗结束
+                            // 可扩展：Log(String), Progress(f32), Error(String), Done 等
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct ArmySnapshot {
+    pub seq: u64, // 阵型序号：开局为 0，之后每个有变化的时刻加 1
     pub enemys: Vec<VecDeque<Unit>>,
     pub allys: Vec<VecDeque<Unit>>,
-    pub enemys_num: usize,
-    pub allys_num: usize,
+    pub enemys_num: usize, // 敌方存活数量
+    pub allys_num: usize,  // 我方存活数量
+    pub enemys_hp: u128,   // 敌方剩余总生命
+    pub allys_hp: u128,    // 我方剩余总生命
 }
+impl ArmySnapshot {
+    pub fn from_army(army: &Army) -> Self {
+        let mut snapshot = Self {
+            enemys: army.enemys.clone(),
+            allys: army.allys.clone(),
+            ..Default::default()
+        };
+        snapshot.recount();
+        snapshot
+    }
 
-#[derive(Debug)]
+    // 应用增量；序号不连续（丢失或乱序）时不应用并返回 false，需要重新同步
+    // 已应用过的旧增量直接忽略
+    pub fn apply(&mut self, diff: &ArmyDiff) -> bool {
+        if diff.seq <= self.seq {
+            return true;
+        }
+        if diff.from != self.seq {
+            return false;
+        }
+        for change in &diff.changes {
+            match change {
+                ArmyChange::Hp { faction, col, hp } => {
+                    if let Some(unit) = self
+                        .cols_mut(*faction)
+                        .get_mut(*col)
+                        .and_then(VecDeque::front_mut)
+                    {
+                        unit.hp = *hp;
+                    }
+                }
+                ArmyChange::Died { faction, col } => {
+                    if let Some(column) = self.cols_mut(*faction).get_mut(*col) {
+                        column.pop_front();
+                    }
+                }
+                ArmyChange::ColumnRemoved { faction, col } => {
+                    let cols = self.cols_mut(*faction);
+                    if *col < cols.len() {
+                        cols.remove(*col);
+                    }
+                }
+                ArmyChange::Reinforce { faction, col, unit } => {
+                    let cols = self.cols_mut(*faction);
+                    match cols.get_mut(*col) {
+                        Some(column) => column.push_back(unit.clone()),
+                        None => cols.push(VecDeque::from([unit.clone()])),
+                    }
+                }
+            }
+        }
+        self.seq = diff.seq;
+        self.recount();
+        true
+    }
+
+    fn cols_mut(&mut self, faction: Faction) -> &mut Vec<VecDeque<Unit>> {
+        match faction {
+            Faction::Ally => &mut self.allys,
+            Faction::Enemy => &mut self.enemys,
+        }
+    }
+
+    // 重新统计双方数量和总生命
+    pub fn recount(&mut self) {
+        (self.enemys_num, self.enemys_hp) = totals(&self.enemys);
+        (self.allys_num, self.allys_hp) = totals(&self.allys);
+    }
+
+    // 我方总生命占双方总和的比例，双方都为空时为 0.5
+    pub fn ally_hp_ratio(&self) -> f32 {
+        let total = self.allys_hp + self.enemys_hp;
+        if total == 0 {
+            return 0.5;
+        }
+        (self.allys_hp as f64 / total as f64) as f32
+    }
+}
+
+// 阵型变化，只有每列第一行的单位会受伤、回血或阵亡
+#[derive(Debug, Clone)]
+pub enum ArmyChange {
+    Hp {
+        faction: Faction,
+        col: usize,
+        hp: u128,
+    }, // 第 col 列第一行单位的当前生命
+    Died {
+        faction: Faction,
+        col: usize,
+    }, // 第 col 列第一行单位阵亡，后排前移
+    ColumnRemoved {
+        faction: Faction,
+        col: usize,
+    }, // 第 col 列已空并移除，右侧列左移
+    Reinforce {
+        faction: Faction,
+        col: usize,
+        unit: Unit,
+    }, // 援军加入第 col 列末尾，col 超出时新建一列
+}
+
+// 一段时间内的全部阵型变化：作用于序号为 from 的阵型，应用后序号变为 seq
+// 正常每个时刻一个增量（seq = from + 1），界面来不及接收时多个增量会合并
+#[derive(Debug, Clone)]
+pub struct ArmyDiff {
+    pub from: u64,
+    pub seq: u64,
+    pub changes: Vec<ArmyChange>,
+}
+
+// 单位数量和总生命
+fn totals(cols: &[VecDeque<Unit>]) -> (usize, u128) {
+    cols.iter()
+        .flatten()
+        .fold((0, 0), |(num, hp), u| (num + 1, hp + u.hp))
+}
+
+// 战斗事件，at 为虚拟时钟（毫秒），与真实时间无关
+#[derive(Debug, Clone, PartialEq)]
 pub enum BattleEvent {
-    ATK {
-        id: u128,
-        timestamp: Instant, // 可选：用于去重或排序
-    },
-    DEF {
-        id: u128,
-        amount: u128,
-        timestamp: Instant, // 可选：用于去重或排序
-    },
+    ATK { id: UnitId, at: u64 },                // 出手
+    DEF { id: UnitId, amount: u128, at: u64 },  // 受到普通伤害
+    Crit { id: UnitId, amount: u128, at: u64 }, // 受到暴击伤害
+    Miss { id: UnitId, at: u64 },               // 攻击未命中（id 为被攻击者）
+    Dodge { id: UnitId, at: u64 },              // 闪避
+    Heal { id: UnitId, amount: u128, at: u64 }, // 回血（生命偷取）
 }
 impl BattleEvent {
-    pub fn atk(id: u128) -> BattleEvent {
-        BattleEvent::ATK {
-            id,
-            timestamp: Instant::now(), // 可选：用于去重或排序
+    pub fn atk(id: UnitId, at: u64) -> BattleEvent {
+        BattleEvent::ATK { id, at }
+    }
+    pub fn def(id: UnitId, amount: u128, at: u64) -> BattleEvent {
+        BattleEvent::DEF { id, amount, at }
+    }
+    // 事件对应的单位
+    pub fn id(&self) -> UnitId {
+        match self {
+            BattleEvent::ATK { id, .. }
+            | BattleEvent::DEF { id, .. }
+            | BattleEvent::Crit { id, .. }
+            | BattleEvent::Miss { id, .. }
+            | BattleEvent::Dodge { id, .. }
+            | BattleEvent::Heal { id, .. } => *id,
+        }
+    }
+    // 事件发生的虚拟时刻
+    pub fn at(&self) -> u64 {
+        match self {
+            BattleEvent::ATK { at, .. }
+            | BattleEvent::DEF { at, .. }
+            | BattleEvent::Crit { at, .. }
+            | BattleEvent::Miss { at, .. }
+            | BattleEvent::Dodge { at, .. }
+            | BattleEvent::Heal { at, .. } => *at,
+        }
+    }
+}
+
+// 单位战斗统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitStats {
+    pub id: UnitId,
+    pub name: Arc<str>,
+    pub faction: Faction,
+    pub damage_dealt: u128, // 造成伤害（不含溢出）
+    pub damage_taken: u128, // 承受伤害（不含溢出）
+    pub healed: u128,       // 生命偷取回复量
+    pub kills: u32,         // 击杀数
+    pub attacks: u32,       // 出手次数
+}
+impl UnitStats {
+    fn new(unit: &Unit, faction: Faction) -> Self {
+        Self {
+            id: unit.id,
+            name: unit.name.clone(),
+            faction,
+            damage_dealt: 0,
+            damage_taken: 0,
+            healed: 0,
+            kills: 0,
+            attacks: 0,
+        }
+    }
+}
+
+// 战斗结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct BattleReport {
+    pub seed: u64,
+    pub winner: Option<Faction>, // 平局为 None：双方开局即为空，或超过虚拟时长上限
+    pub duration_ms: u64,        // 虚拟时长
+    pub allys_survivors: Vec<Unit>, // 我方存活单位
+    pub enemys_survivors: Vec<Unit>, // 敌方存活单位
+    pub units: Vec<UnitStats>,   // 所有单位统计，按 id 排序
+}
+
+// 战斗速度：虚拟时间相对真实时间的倍率，Skip 直接算出结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BattleSpeed {
+    #[default]
+    X1,
+    X2,
+    X4,
+    Skip,
+}
+impl BattleSpeed {
+    // 倍率，Skip 没有倍率
+    pub fn multiplier(self) -> Option<f32> {
+        match self {
+            BattleSpeed::X1 => Some(1.0),
+            BattleSpeed::X2 => Some(2.0),
+            BattleSpeed::X4 => Some(4.0),
+            BattleSpeed::Skip => None,
+        }
+    }
+
+    // 循环切换：1x -> 2x -> 4x -> 跳过 -> 1x
+    pub fn next(self) -> Self {
+        match self {
+            BattleSpeed::X1 => BattleSpeed::X2,
+            BattleSpeed::X2 => BattleSpeed::X4,
+            BattleSpeed::X4 => BattleSpeed::Skip,
+            BattleSpeed::Skip => BattleSpeed::X1,
         }
     }
-    pub fn def(id: u128, amount: u128) -> BattleEvent {
-        BattleEvent::DEF {
-            id,
-            amount,
-            timestamp: Instant::now(), // 可选：用于去重或排序
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BattleSpeed::X1 => "1x",
+            BattleSpeed::X2 => "2x",
+            BattleSpeed::X4 => "4x",
+            BattleSpeed::Skip => "SKIP",
         }
     }
 }
@@ -56,13 +288,649 @@ pub struct BattleContext {
     pub max_enemy_cols: usize,
     pub max_ally_cols: usize,
     pub army: Army,
-    pub events: Sender<BattleEvent>,   // 事件发送
-    pub army_tx: Sender<ArmySnapshot>, // 帧发送
+    pub tx: Sender<BattleOutput>, // 事件、帧发送
+    pub rng: BattleRng,           // 战斗内所有随机都从这里取
+    pub speed: BattleSpeed,       // 初始速度
+}
+impl BattleContext {
+    pub fn new(army: Army, seed: u64, tx: Sender<BattleOutput>) -> Self {
+        Self {
+            max_enemy_cols: CONFIG.max_enemy_cols.load(Ordering::Relaxed),
+            max_ally_cols: CONFIG.max_ally_cols.load(Ordering::Relaxed),
+            army,
+            tx,
+            rng: BattleRng::new(seed),
+            speed: BattleSpeed::default(),
+        }
+    }
+}
+
+// 虚拟时长上限（毫秒）：打不死的组合（命中率 0、生命偷取 100%、闪避 100% 等）到时以平局结束
+// 正常战斗远达不到，2000 对 2000 单列约 9 小时
+pub const MAX_DURATION_MS: u64 = 24 * 60 * 60 * 1000;
+
+// 战斗模拟：只推进虚拟时钟，不睡眠、不依赖真实时间
+#[derive(Debug)]
+pub struct Battle {
+    army: Army,
+    rng: BattleRng,
+    now: u64,                           // 虚拟时钟（毫秒）
+    next_at: HashMap<UnitId, u64>,      // 单位下一次出手时间：unit.id -> 毫秒
+    stats: BTreeMap<UnitId, UnitStats>, // 单位统计：unit.id -> 统计
+    changes: Vec<ArmyChange>,           // 上次取出之后的阵型变化
+    max_enemy_cols: usize,              // 敌方最大列数
+    max_ally_cols: usize,               // 我方最大列数
+    timed_out: bool,                    // 超过虚拟时长上限，平局结束
+}
+impl Battle {
+    // 空列先移除：列号必须与界面阵型一致，一方没有单位时直接结束
+    pub fn new(mut army: Army, rng: BattleRng) -> Self {
+        army.allys.retain(|c| !c.is_empty());
+        army.enemys.retain(|c| !c.is_empty());
+        let mut stats = BTreeMap::new();
+        for (cols, faction) in [(&army.allys, Faction::Ally), (&army.enemys, Faction::Enemy)] {
+            for unit in cols.iter().flatten() {
+                stats.insert(unit.id, UnitStats::new(unit, faction));
+            }
+        }
+        Self {
+            army,
+            rng,
+            now: 0,
+            next_at: HashMap::new(),
+            stats,
+            changes: Vec::new(),
+            max_enemy_cols: usize::MAX,
+            max_ally_cols: usize::MAX,
+            timed_out: false,
+        }
+    }
+
+    // 限制双方列数，超出的列补到前面各列
+    pub fn with_max_cols(mut self, max_enemy_cols: usize, max_ally_cols: usize) -> Self {
+        self.max_enemy_cols = max_enemy_cols.max(1);
+        self.max_ally_cols = max_ally_cols.max(1);
+        model::fit_columns(&mut self.army.enemys, self.max_enemy_cols);
+        model::fit_columns(&mut self.army.allys, self.max_ally_cols);
+        self
+    }
+
+    // 取出累计的阵型变化
+    pub fn take_changes(&mut self) -> Vec<ArmyChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    // 援军加入第 col 列末尾，col 超出时新建一列，已达列数上限时加入最后一列
+    pub fn reinforce(&mut self, faction: Faction, col: usize, unit: Unit) {
+        self.stats.insert(unit.id, UnitStats::new(&unit, faction));
+        let max_cols = match faction {
+            Faction::Ally => self.max_ally_cols,
+            Faction::Enemy => self.max_enemy_cols,
+        };
+        let cols = self.army.cols_mut(faction);
+        let col = col.min(cols.len()).min(max_cols - 1);
+        match cols.get_mut(col) {
+            Some(column) => column.push_back(unit.clone()),
+            None => cols.push(VecDeque::from([unit.clone()])),
+        }
+        self.changes
+            .push(ArmyChange::Reinforce { faction, col, unit });
+    }
+
+    pub fn army(&self) -> &Army {
+        &self.army
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.timed_out || self.army.allys.is_empty() || self.army.enemys.is_empty()
+    }
+
+    pub fn winner(&self) -> Option<Faction> {
+        if self.timed_out {
+            return None;
+        }
+        match (self.army.allys.is_empty(), self.army.enemys.is_empty()) {
+            (false, true) => Some(Faction::Ally),
+            (true, false) => Some(Faction::Enemy),
+            _ => None,
+        }
+    }
+
+    pub fn report(&self) -> BattleReport {
+        BattleReport {
+            seed: self.rng.seed(),
+            winner: self.winner(),
+            duration_ms: self.now,
+            allys_survivors: self.army.allys.iter().flatten().cloned().collect(),
+            enemys_survivors: self.army.enemys.iter().flatten().cloned().collect(),
+            units: self.stats.values().cloned().collect(),
+        }
+    }
+
+    // 推进到下一个出手时刻并结算，返回该时刻产生的事件；战斗已结束返回 None
+    // 下一个出手时刻超过 MAX_DURATION_MS 时以平局结束
+    // 我方第一行从左到右依次攻击，攻击敌方第一行随机单位；敌方同理
+    pub fn step(&mut self) -> Option<Vec<BattleEvent>> {
+        if self.is_over() {
+            return None;
+        }
+        let Self {
+            army,
+            rng,
+            now,
+            next_at,
+            stats,
+            changes,
+            timed_out,
+            ..
+        } = self;
+
+        // 新上到第一行的单位从当前时刻开始计时
+        for unit in front_row(&army.allys).chain(front_row(&army.enemys)) {
+            next_at
+                .entry(unit.id)
+                .or_insert(*now + unit.attack_interval());
+        }
+        let at = front_row(&army.allys)
+            .chain(front_row(&army.enemys))
+            .filter_map(|u| next_at.get(&u.id).copied())
+            .min()?;
+        if at > MAX_DURATION_MS {
+            *now = MAX_DURATION_MS;
+            *timed_out = true;
+            return None;
+        }
+        *now = at;
+
+        // 本时刻所有到期的出手者（先我方后敌方，各自从左到右）
+        let attackers: Vec<(Faction, UnitId)> = front_row(&army.allys)
+            .map(|u| (Faction::Ally, u.id))
+            .chain(front_row(&army.enemys).map(|u| (Faction::Enemy, u.id)))
+            .filter(|(_, id)| next_at.get(id) == Some(&at))
+            .collect();
+
+        let mut events = Vec::new();
+        for (faction, id) in attackers {
+            let foe_faction = faction.opponent();
+            let (own, foe) = match faction {
+                Faction::Ally => (&mut army.allys, &mut army.enemys),
+                Faction::Enemy => (&mut army.enemys, &mut army.allys),
+            };
+            // 出手者可能已在本时刻被击杀
+            let Some((attacker_col, attacker)) = own
+                .iter_mut()
+                .filter_map(VecDeque::front_mut)
+                .enumerate()
+                .find(|(_, u)| u.id == id)
+            else {
+                continue;
+            };
+            if foe.is_empty() {
+                break;
+            }
+            next_at.insert(id, at + attacker.attack_interval());
+
+            let target_col = rng.gen_index(foe.len());
+            let Some(target) = foe[target_col].front_mut() else {
+                continue;
+            };
+            events.push(BattleEvent::atk(id, at));
+            if let Some(s) = stats.get_mut(&id) {
+                s.attacks += 1;
+            }
+
+            // 命中 -> 闪避 -> 暴击 -> 生命偷取
+            if !rng.chance(attacker.hit_rate) {
+                events.push(BattleEvent::Miss { id: target.id, at });
+                continue;
+            }
+            if rng.chance(target.dodge_rate) {
+                events.push(BattleEvent::Dodge { id: target.id, at });
+                continue;
+            }
+            let damage = if rng.chance(attacker.crit_rate) {
+                let amount = attacker.calculate_crit_damage_to(target);
+                events.push(BattleEvent::Crit {
+                    id: target.id,
+                    amount,
+                    at,
+                });
+                amount
+            } else {
+                let amount = attacker.calculate_damage_to(target);
+                events.push(BattleEvent::def(target.id, amount, at));
+                amount
+            };
+            let dealt = damage.min(target.hp);
+            target.hp -= dealt;
+            if dealt > 0 {
+                changes.push(ArmyChange::Hp {
+                    faction: foe_faction,
+                    col: target_col,
+                    hp: target.hp,
+                });
+            }
+
+            let healed = attacker.heal((dealt as f64 * attacker.life_steal.max(0.0)) as u128);
+            if healed > 0 {
+                events.push(BattleEvent::Heal {
+                    id,
+                    amount: healed,
+                    at,
+                });
+                changes.push(ArmyChange::Hp {
+                    faction,
+                    col: attacker_col,
+                    hp: attacker.hp,
+                });
+            }
+
+            if let Some(s) = stats.get_mut(&id) {
+                s.damage_dealt += dealt;
+                s.healed += healed;
+                s.kills += u32::from(!target.is_alive());
+            }
+            if let Some(s) = stats.get_mut(&target.id) {
+                s.damage_taken += dealt;
+            }
+
+            if !target.is_alive() {
+                next_at.remove(&target.id);
+                foe[target_col].pop_front();
+                changes.push(ArmyChange::Died {
+                    faction: foe_faction,
+                    col: target_col,
+                });
+                if foe[target_col].is_empty() {
+                    foe.remove(target_col);
+                    changes.push(ArmyChange::ColumnRemoved {
+                        faction: foe_faction,
+                        col: target_col,
+                    });
+                }
+            }
+        }
+        Some(events)
+    }
+}
+
+// 无界面快速模拟，直接得到战斗结果（测试、数值平衡用）
+pub fn simulate(army: Army, seed: u64) -> BattleReport {
+    let mut battle = Battle::new(army, BattleRng::new(seed));
+    while battle.step().is_some() {}
+    battle.report()
+}
+
+// 战斗控制指令
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BattleControl {
+    Pause,              // 暂停虚拟时钟
+    Resume,             // 继续
+    Speed(BattleSpeed), // 速度倍率，2x 表示虚拟时间是真实时间的两倍
+    Resync,             // 界面发现增量序号不连续，请求重新发送全量快照
+    Cancel,             // 结束战斗任务
+}
+
+// 战斗任务句柄：通过控制通道暂停、继续、调速、取消；句柄被丢弃时任务随之中止
+#[derive(Debug)]
+pub struct BattleHandle {
+    control: Sender<BattleControl>,
+    task: JoinHandle<()>,
+}
+impl BattleHandle {
+    fn send(&self, control: BattleControl) {
+        // 任务已结束时通道关闭，忽略即可
+        let _ = self.control.send(control);
+    }
+
+    pub fn pause(&self) {
+        self.send(BattleControl::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.send(BattleControl::Resume);
+    }
+
+    pub fn set_speed(&self, speed: BattleSpeed) {
+        self.send(BattleControl::Speed(speed));
+    }
+
+    pub fn resync(&self) {
+        self.send(BattleControl::Resync);
+    }
+
+    // 通知任务在下一个等待点退出，不再发送结算
+    pub fn cancel(&self) {
+        self.send(BattleControl::Cancel);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+impl Drop for BattleHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// 跳过时每算这么多步让出一次，保证取消和中止能及时生效
+const SKIP_CHUNK: usize = 1024;
+
+// 等待结果
+enum Pace {
+    Continue, // 时间到，继续下一步
+    Skip,     // 跳过剩余战斗
+    Cancel,   // 取消
 }
+
+// 按控制指令把虚拟时间换算为真实等待时间
+struct Pacer {
+    rx: Receiver<BattleControl>,
+    paused: bool,
+    speed: BattleSpeed,
+    resync: bool, // 下一个时刻发送全量快照
+}
+impl Pacer {
+    // 处理一条指令，返回 false 表示取消
+    fn apply(&mut self, control: BattleControl) -> bool {
+        match control {
+            BattleControl::Pause => self.paused = true,
+            BattleControl::Resume => self.paused = false,
+            BattleControl::Speed(speed) => self.speed = speed,
+            BattleControl::Resync => self.resync = true,
+            BattleControl::Cancel => return false,
+        }
+        true
+    }
+
+    // 不等待，处理已收到的指令；取消或句柄被丢弃时返回 false
+    fn poll(&mut self) -> bool {
+        loop {
+            match self.rx.try_recv() {
+                Ok(control) if self.apply(control) => {}
+                Err(TryRecvError::Empty) => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    // 等待 delay 毫秒虚拟时间，期间响应控制指令；取消或句柄被丢弃时返回 Cancel
+    async fn wait(&mut self, delay: u64) -> Pace {
+        let mut remaining = delay as f64; // 剩余虚拟毫秒
+        loop {
+            let Some(speed) = self.speed.multiplier() else {
+                return Pace::Skip;
+            };
+            if self.paused {
+                match self.rx.recv_async().await {
+                    Ok(control) if self.apply(control) => continue,
+                    _ => return Pace::Cancel,
+                }
+            }
+            if remaining <= 0.0 {
+                return Pace::Continue;
+            }
+            let speed = speed as f64;
+            let start = Instant::now();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(remaining / speed / 1000.0)) => {
+                    return Pace::Continue;
+                }
+                control = self.rx.recv_async() => {
+                    remaining -= start.elapsed().as_secs_f64() * 1000.0 * speed;
+                    match control {
+                        Ok(control) if self.apply(control) => {}
+                        _ => return Pace::Cancel,
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 实时节奏层：按虚拟时钟的间隔睡眠，开局发送全量快照，之后发送事件和阵型增量
 pub struct BattleExecutor;
 impl BattleExecutor {
-    pub async fn run(&self, ctx: BattleContext) {
-        let army = ctx.army;
-        // 我方第一行从左到右依次攻击，攻击敌方第一行随机单位
+    // 在全局运行时上开始战斗，返回控制句柄
+    pub fn spawn(ctx: BattleContext) -> BattleHandle {
+        let (control, rx) = flume::unbounded();
+        let task = global_tokio_runtime().spawn(async move {
+            BattleExecutor.run(ctx, rx).await;
+        });
+        BattleHandle { control, task }
+    }
+
+    pub async fn run(&self, ctx: BattleContext, control: Receiver<BattleControl>) {
+        let BattleContext {
+            max_enemy_cols,
+            max_ally_cols,
+            army,
+            tx,
+            rng,
+            speed,
+        } = ctx;
+        let mut battle = Battle::new(army, rng).with_max_cols(max_enemy_cols, max_ally_cols);
+        let mut pacer = Pacer {
+            rx: control,
+            paused: false,
+            speed,
+            resync: false,
+        };
+        let mut outbox = Outbox::new(tx);
+        // 当前阵型序号，全量快照带上它
+        let mut seq = 0;
+        let snapshot = |battle: &Battle, seq| {
+            BattleOutput::ArmySnapshot(ArmySnapshot {
+                seq,
+                ..ArmySnapshot::from_army(battle.army())
+            })
+        };
+
+        outbox.push(snapshot(&battle, seq));
+        if !outbox.flush() {
+            return;
+        }
+
+        let mut last = battle.now();
+        while let Some(events) = battle.step() {
+            let delay = battle.now() - last;
+            last = battle.now();
+            match pacer.wait(delay).await {
+                Pace::Continue => {}
+                Pace::Cancel => {
+                    log::info!("战斗已取消，虚拟时间 {} 毫秒", battle.now());
+                    return;
+                }
+                // 跳过：直接算完剩余战斗，只发送最终快照；分段计算，段间让出并处理指令
+                Pace::Skip => {
+                    let mut steps = 0;
+                    while battle.step().is_some() {
+                        steps += 1;
+                        if steps % SKIP_CHUNK == 0 {
+                            tokio::task::yield_now().await;
+                            if !pacer.poll() {
+                                log::info!("战斗已取消，虚拟时间 {} 毫秒", battle.now());
+                                return;
+                            }
+                        }
+                    }
+                    battle.take_changes();
+                    outbox.push(snapshot(&battle, seq + 1));
+                    break;
+                }
+            }
+
+            outbox.extend(events.into_iter().map(BattleOutput::BattleEvent));
+            let changes = battle.take_changes();
+            if std::mem::take(&mut pacer.resync) {
+                seq += 1;
+                outbox.push(snapshot(&battle, seq));
+            } else if !changes.is_empty() {
+                seq += 1;
+                outbox.push(BattleOutput::ArmyDiff(ArmyDiff {
+                    from: seq - 1,
+                    seq,
+                    changes,
+                }));
+            }
+            // 不等待界面：发不出去的留在发件箱里合并
+            if !outbox.flush() {
+                return;
+            }
+        }
+        let report = battle.report();
+        log::info!(
+            "战斗结束：{:?}，用时 {} 毫秒，种子 {}",
+            report.winner,
+            report.duration_ms,
+            report.seed
+        );
+        outbox.finish(report).await;
+    }
+}
+
+// 每列第一行的单位
+fn front_row(cols: &[VecDeque<Unit>]) -> impl Iterator<Item = &Unit> {
+    cols.iter().filter_map(VecDeque::front)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 跑完整场战斗，返回全部事件和结算
+    fn run_to_end(army: Army, seed: u64) -> (Vec<BattleEvent>, BattleReport) {
+        let mut battle = Battle::new(army, BattleRng::new(seed));
+        let mut events = Vec::new();
+        while let Some(step) = battle.step() {
+            events.extend(step);
+        }
+        (events, battle.report())
+    }
+
+    #[test]
+    fn same_army_and_seed_replays_identically() {
+        let army = Army {
+            enemys: model::test(60, 5),
+            allys: model::test(60, 5),
+        };
+        let (events, report) = run_to_end(army.clone(), 42);
+        let (replay_events, replay_report) = run_to_end(army, 42);
+        assert!(!events.is_empty());
+        assert!(report.winner.is_some());
+        assert_eq!(events, replay_events);
+        assert_eq!(report, replay_report);
+    }
+
+    // 打不死的组合到达时长上限后以平局结束
+    #[test]
+    fn stalled_battle_ends_in_draw() {
+        let stalls: [fn(&mut Unit); 2] = [
+            |u| u.hit_rate = 0.0,
+            // 双方伤害相同且全额回血
+            |u| {
+                u.life_steal = 1.0;
+                u.hit_rate = 1.0;
+                u.dodge_rate = 0.0;
+                u.crit_rate = 0.0;
+            },
+        ];
+        for stall in stalls {
+            let mut army = Army {
+                enemys: model::test(1, 1),
+                allys: model::test(1, 1),
+            };
+            army.enemys
+                .iter_mut()
+                .chain(&mut army.allys)
+                .flatten()
+                .for_each(stall);
+            let report = simulate(army, 42);
+            assert_eq!(report.winner, None);
+            assert_eq!(report.duration_ms, MAX_DURATION_MS);
+        }
+    }
+
+    // 输入中的空列：没有单位的一方直接判负，回血的列号与界面阵型一致
+    #[test]
+    fn empty_columns_are_dropped() {
+        let army = Army {
+            enemys: model::test(3, 1),
+            allys: vec![VecDeque::new()],
+        };
+        let report = simulate(army, 42);
+        assert_eq!(report.winner, Some(Faction::Enemy));
+        assert_eq!(report.duration_ms, 0);
+
+        let mut allys = model::test(6, 2);
+        for unit in allys.iter_mut().flatten() {
+            unit.life_steal = 0.5;
+            unit.hit_rate = 1.0;
+        }
+        allys.insert(0, VecDeque::new());
+        let army = Army {
+            enemys: vec![VecDeque::new(), model::test(30, 1).remove(0)],
+            allys,
+        };
+        let mut battle = Battle::new(army, BattleRng::new(42));
+        let mut ui = ArmySnapshot::from_army(battle.army());
+        let mut healed = false;
+        while let Some(events) = battle.step() {
+            healed |= events.iter().any(|e| matches!(e, BattleEvent::Heal { .. }));
+            let seq = ui.seq + 1;
+            assert!(ui.apply(&ArmyDiff {
+                from: ui.seq,
+                seq,
+                changes: battle.take_changes(),
+            }));
+            assert_eq!(
+                ui,
+                ArmySnapshot {
+                    seq,
+                    ..ArmySnapshot::from_army(battle.army())
+                }
+            );
+        }
+        assert!(healed);
+    }
+
+    // 跳过一场很长的战斗时仍能取消
+    #[test]
+    fn skip_can_be_cancelled() {
+        let mut army = Army {
+            enemys: model::test(1, 1),
+            allys: model::test(1, 1),
+        };
+        // 每毫秒出手且永远打不中，跳过要算到时长上限
+        army.enemys
+            .iter_mut()
+            .chain(&mut army.allys)
+            .flatten()
+            .for_each(|u| {
+                u.hit_rate = 0.0;
+                u.speek = 1;
+                u.attack_speed = 1.0;
+            });
+        let (tx, _rx) = flume::unbounded();
+        let ctx = BattleContext {
+            speed: BattleSpeed::Skip,
+            ..BattleContext::new(army, 42, tx)
+        };
+        let handle = BattleExecutor::spawn(ctx);
+        std::thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+        let start = Instant::now();
+        while !handle.is_finished() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "取消后战斗任务没有退出"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
     }
 }
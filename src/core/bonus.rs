@@ -0,0 +1,207 @@
+// 加成系统：科技、装备、区域等来源给资源产量和单位属性提供固定值或百分比加成
+// 最终值 = (基础值 + 固定加成之和) × (1 + 百分比加成之和)
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::UnitTemp;
+
+// 加成来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BonusSource {
+    Tech,      // 科技
+    Equipment, // 装备
+    Area,      // 区域
+}
+
+// 单位属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UnitStat {
+    Atk,
+    Hp,
+    Def,
+    CritRate,
+    CritDamage,
+    HitRate,
+    DodgeRate,
+    AttackSpeed,
+    LifeSteal,
+}
+
+// 加成目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BonusTarget {
+    ResourceChange(u32), // 资源每周期产量：资源 id
+    // 单位属性：temp_id 为 BaseUnitTemp.id，None 表示所有单位
+    UnitStat {
+        temp_id: Option<u32>,
+        stat: UnitStat,
+    },
+}
+impl BonusTarget {
+    // 单位目标是否作用于该模板
+    fn matches(&self, target: &BonusTarget) -> bool {
+        match (self, target) {
+            (
+                BonusTarget::UnitStat { temp_id, stat },
+                BonusTarget::UnitStat {
+                    temp_id: Some(id),
+                    stat: s,
+                },
+            ) => stat == s && temp_id.is_none_or(|t| t == *id),
+            _ => self == target,
+        }
+    }
+}
+
+// 来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bonus {
+    pub id: u32,                   // 唯一标识符
+    pub name: String,              // 来源名称
+    pub description: String,       // 来源描述
+    pub source: BonusSource,       // 来源类型
+    pub area_name: String,         // 区域名称（非区域来源为空）
+    pub targets: Vec<BonusTarget>, // 目标
+    pub value: f64,                // 固定加成
+    pub rate: f64,                 // 百分比加成，0.1 表示 +10%
+}
+
+// 某个最终值的构成，用于界面提示
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BonusBreakdown {
+    pub base: f64,
+    pub flat: Vec<(String, f64)>, // 来源名称 -> 固定加成
+    pub rate: Vec<(String, f64)>, // 来源名称 -> 百分比加成
+    pub total: f64,
+}
+impl fmt::Display for BonusBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Base {}", trim(self.base))?;
+        for (name, v) in &self.flat {
+            write!(f, "\n{:+} {}", trim(*v), name)?;
+        }
+        for (name, r) in &self.rate {
+            write!(f, "\n{:+}% {}", trim(r * 100.0), name)?;
+        }
+        write!(f, "\n= {}", trim(self.total))
+    }
+}
+
+// 保留两位小数，去掉浮点噪声
+fn trim(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+// 来源管理
+#[derive(Debug, Clone, Default)]
+pub struct BonusManager {
+    inner: Vec<Bonus>, // 来源列表
+}
+impl BonusManager {
+    // 添加来源，id 相同则替换
+    pub fn add(&mut self, bonus: Bonus) {
+        self.remove(bonus.id);
+        self.inner.push(bonus);
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Bonus> {
+        let idx = self.inner.iter().position(|b| b.id == id)?;
+        Some(self.inner.remove(idx))
+    }
+
+    // 移除某类来源（如卸下全部装备）
+    pub fn remove_source(&mut self, source: BonusSource) {
+        self.inner.retain(|b| b.source != source);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Bonus> {
+        self.inner.iter()
+    }
+
+    // 计算最终值并给出构成
+    pub fn breakdown(&self, target: BonusTarget, base: f64) -> BonusBreakdown {
+        let mut out = BonusBreakdown {
+            base,
+            ..Default::default()
+        };
+        for b in &self.inner {
+            if !b.targets.iter().any(|t| t.matches(&target)) {
+                continue;
+            }
+            if b.value != 0.0 {
+                out.flat.push((b.name.clone(), b.value));
+            }
+            if b.rate != 0.0 {
+                out.rate.push((b.name.clone(), b.rate));
+            }
+        }
+        let flat: f64 = out.flat.iter().map(|(_, v)| v).sum();
+        let rate: f64 = out.rate.iter().map(|(_, r)| r).sum();
+        out.total = ((base + flat) * (1.0 + rate)).max(0.0);
+        out
+    }
+
+    pub fn apply(&self, target: BonusTarget, base: f64) -> f64 {
+        self.breakdown(target, base).total
+    }
+
+    // 整数值（攻击、生命、产量等）向下取整
+    pub fn apply_u128(&self, target: BonusTarget, base: u128) -> u128 {
+        self.apply(target, base as f64) as u128
+    }
+
+    // 账号模板叠加加成
+    pub fn apply_unit(&self, temp: &UnitTemp) -> UnitTemp {
+        let target = |stat| BonusTarget::UnitStat {
+            temp_id: Some(temp.base_temp_id),
+            stat,
+        };
+        let rate = |stat, v: f64| self.apply(target(stat), v).clamp(0.0, 1.0);
+        UnitTemp {
+            atk: self.apply_u128(target(UnitStat::Atk), temp.atk),
+            hp: self.apply_u128(target(UnitStat::Hp), temp.hp).max(1),
+            def: self.apply_u128(target(UnitStat::Def), temp.def),
+            crit_rate: rate(UnitStat::CritRate, temp.crit_rate),
+            crit_damage: self.apply(target(UnitStat::CritDamage), temp.crit_damage),
+            hit_rate: rate(UnitStat::HitRate, temp.hit_rate),
+            dodge_rate: rate(UnitStat::DodgeRate, temp.dodge_rate),
+            attack_speed: self.apply(target(UnitStat::AttackSpeed), temp.attack_speed),
+            life_steal: self.apply(target(UnitStat::LifeSteal), temp.life_steal),
+            ..temp.clone()
+        }
+    }
+}
+
+// 默认加成
+pub fn default_bonuses() -> Vec<Bonus> {
+    vec![
+        Bonus {
+            id: 1,
+            name: "Plains".to_string(),
+            description: "Fertile land".to_string(),
+            source: BonusSource::Area,
+            area_name: "Plains".to_string(),
+            targets: vec![
+                BonusTarget::ResourceChange(1),
+                BonusTarget::ResourceChange(2),
+            ],
+            value: 1.0,
+            rate: 0.2,
+        },
+        Bonus {
+            id: 2,
+            name: "Forging".to_string(),
+            description: "Sharper blades".to_string(),
+            source: BonusSource::Tech,
+            area_name: String::new(),
+            targets: vec![BonusTarget::UnitStat {
+                temp_id: None,
+                stat: UnitStat::Atk,
+            }],
+            value: 2.0,
+            rate: 0.1,
+        },
+    ]
+}
@@ -0,0 +1,350 @@
+// 战斗输出投递：战斗任务只做非阻塞发送，界面卡顿或切到后台时不会拖慢模拟
+// 发不出去的输出留在发件箱里合并：阵型只保留最新生命，同一单位的同类事件合并为一条
+
+use std::{
+    collections::{HashMap, VecDeque},
+    mem::{self, Discriminant},
+    time::Instant,
+};
+
+use flume::{Sender, TrySendError};
+
+use crate::{
+    core::batttle::{ArmyChange, ArmyDiff, ArmySnapshot, BattleEvent, BattleOutput, BattleReport},
+    model::{Faction, UnitId},
+};
+
+// 战斗通道容量
+pub const CHANNEL_CAPACITY: usize = 64;
+// 界面保留的事件条数，更早的事件丢弃
+pub const EVENT_HISTORY: usize = 256;
+
+// 发件箱：通道满时暂存输出，下次发送前合并
+#[derive(Debug)]
+pub struct Outbox {
+    tx: Sender<BattleOutput>,
+    pending: VecDeque<BattleOutput>,
+}
+impl Outbox {
+    pub fn new(tx: Sender<BattleOutput>) -> Self {
+        Self {
+            tx,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, out: BattleOutput) {
+        self.pending.push_back(out);
+    }
+
+    pub fn extend(&mut self, outs: impl IntoIterator<Item = BattleOutput>) {
+        self.pending.extend(outs);
+    }
+
+    // 通道和发件箱中尚未被界面取走的输出数量
+    pub fn backlog(&self) -> usize {
+        self.tx.len() + self.pending.len()
+    }
+
+    // 尽量发送，通道满时把剩下的合并后留到下次；界面已关闭通道时返回 false
+    pub fn flush(&mut self) -> bool {
+        while let Some(out) = self.pending.pop_front() {
+            match self.tx.try_send(out) {
+                Ok(()) => {}
+                Err(TrySendError::Full(out)) => {
+                    self.pending.push_front(out);
+                    self.coalesce();
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+        true
+    }
+
+    // 战斗结束：模拟已经完成，等待界面取走剩余输出和结算
+    pub async fn finish(mut self, report: BattleReport) {
+        self.coalesce();
+        self.pending.push_back(BattleOutput::Finished(report));
+        for out in self.pending {
+            if self.tx.send_async(out).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // 合并积压输出：事件按单位和类型合并，阵型快照吸收之后的增量，多个增量合为一个
+    fn coalesce(&mut self) {
+        if self.pending.len() < 2 {
+            return;
+        }
+        let mut events = EventMerger::default();
+        let mut snapshot: Option<ArmySnapshot> = None;
+        let mut diff: Option<ArmyDiff> = None;
+        let mut rest = Vec::new();
+        for out in self.pending.drain(..) {
+            match out {
+                BattleOutput::BattleEvent(event) => events.push(event),
+                BattleOutput::ArmySnapshot(s) => {
+                    snapshot = Some(s);
+                    diff = None;
+                }
+                BattleOutput::ArmyDiff(d) => match (&mut snapshot, &mut diff) {
+                    (Some(s), _) => {
+                        s.apply(&d);
+                    }
+                    (None, Some(merged)) => merge_diff(merged, d),
+                    (None, None) => diff = Some(d),
+                },
+                other => rest.push(other),
+            }
+        }
+        self.pending
+            .extend(events.finish().map(BattleOutput::BattleEvent));
+        self.pending
+            .extend(snapshot.map(BattleOutput::ArmySnapshot));
+        self.pending.extend(diff.map(BattleOutput::ArmyDiff));
+        self.pending.extend(rest);
+    }
+}
+
+// 同一单位同类事件合并：伤害、回血累加，时刻取最新；保持首次出现的顺序
+#[derive(Default)]
+struct EventMerger {
+    events: Vec<BattleEvent>,
+    index: HashMap<(UnitId, Discriminant<BattleEvent>), usize>,
+}
+impl EventMerger {
+    fn push(&mut self, event: BattleEvent) {
+        let key = (event.id(), mem::discriminant(&event));
+        match self.index.get(&key) {
+            Some(&i) => merge_event(&mut self.events[i], event),
+            None => {
+                self.index.insert(key, self.events.len());
+                self.events.push(event);
+            }
+        }
+    }
+
+    fn finish(self) -> impl Iterator<Item = BattleEvent> {
+        self.events.into_iter()
+    }
+}
+
+fn merge_event(into: &mut BattleEvent, event: BattleEvent) {
+    use BattleEvent::*;
+    match (into, event) {
+        (ATK { at, .. }, ATK { at: t, .. })
+        | (Miss { at, .. }, Miss { at: t, .. })
+        | (Dodge { at, .. }, Dodge { at: t, .. }) => *at = t,
+        (
+            DEF { amount, at, .. },
+            DEF {
+                amount: a, at: t, ..
+            },
+        )
+        | (
+            Crit { amount, at, .. },
+            Crit {
+                amount: a, at: t, ..
+            },
+        )
+        | (
+            Heal { amount, at, .. },
+            Heal {
+                amount: a, at: t, ..
+            },
+        ) => {
+            *amount = amount.saturating_add(a);
+            *at = t;
+        }
+        _ => {}
+    }
+}
+
+// 把 next 接到 merged 后面；同一列第一行的生命变化只保留最新一次
+// 阵亡、移除列、援军会改变列的位置，之后该阵营的生命变化重新记录
+fn merge_diff(merged: &mut ArmyDiff, next: ArmyDiff) {
+    merged.seq = next.seq;
+    let mut changes = Vec::with_capacity(merged.changes.len() + next.changes.len());
+    let mut last_hp: HashMap<(Faction, usize), usize> = HashMap::new();
+    for change in mem::take(&mut merged.changes)
+        .into_iter()
+        .chain(next.changes)
+    {
+        match change {
+            ArmyChange::Hp { faction, col, hp } => match last_hp.get(&(faction, col)) {
+                Some(&i) => {
+                    if let ArmyChange::Hp { hp: old, .. } = &mut changes[i] {
+                        *old = hp;
+                    }
+                }
+                None => {
+                    last_hp.insert((faction, col), changes.len());
+                    changes.push(change);
+                }
+            },
+            ArmyChange::Died { faction, .. }
+            | ArmyChange::ColumnRemoved { faction, .. }
+            | ArmyChange::Reinforce { faction, .. } => {
+                last_hp.retain(|(f, _), _| *f != faction);
+                changes.push(change);
+            }
+        }
+    }
+    merged.changes = changes;
+}
+
+// 界面收到的事件，seq 从 1 开始递增，换场也不重置
+#[derive(Debug, Clone)]
+pub struct HistoryEvent {
+    pub seq: u64,
+    pub at: Instant, // 收到事件的真实时间，用于动画
+    pub event: BattleEvent,
+}
+
+// 界面侧事件历史：最多保留 EVENT_HISTORY 条，超出从最早的开始丢弃
+#[derive(Debug, Default)]
+pub struct EventHistory {
+    events: VecDeque<HistoryEvent>,
+    next_seq: u64,
+}
+impl EventHistory {
+    pub fn push(&mut self, at: Instant, event: BattleEvent) {
+        if self.events.len() >= EVENT_HISTORY {
+            self.events.pop_front();
+        }
+        self.next_seq += 1;
+        self.events.push_back(HistoryEvent {
+            seq: self.next_seq,
+            at,
+            event,
+        });
+    }
+
+    // 序号大于 seq 的事件
+    pub fn since(&self, seq: u64) -> impl Iterator<Item = &HistoryEvent> {
+        let start = self.events.partition_point(|e| e.seq <= seq);
+        self.events.range(start..)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HistoryEvent> {
+        self.events.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flume::Receiver;
+
+    use super::*;
+    use crate::{
+        core::{
+            batttle::{Army, Battle},
+            rng::BattleRng,
+        },
+        model,
+    };
+
+    // 界面侧：按收到的快照和增量还原阵型，返回其中合并过的增量个数
+    fn receive(rx: &Receiver<BattleOutput>, army: &mut ArmySnapshot) -> usize {
+        let mut merged = 0;
+        for out in rx.try_iter() {
+            match out {
+                BattleOutput::ArmySnapshot(s) => *army = s,
+                BattleOutput::ArmyDiff(d) => {
+                    merged += usize::from(d.seq > d.from + 1);
+                    assert!(army.apply(&d), "增量 {}..{} 不连续", d.from, d.seq);
+                }
+                _ => {}
+            }
+        }
+        merged
+    }
+
+    // 跑完整场战斗（中途有援军），输出都经过发件箱，界面每 receive_every 个时刻取一次
+    // 返回界面还原的阵型、战斗最终阵型、合并过的增量个数
+    fn replay(
+        tx: Sender<BattleOutput>,
+        rx: Receiver<BattleOutput>,
+        receive_every: usize,
+    ) -> (ArmySnapshot, ArmySnapshot, usize) {
+        let army = Army {
+            enemys: model::test(60, 5),
+            allys: model::test(60, 5),
+        };
+        let mut reinforcements = model::test(4, 1).remove(0);
+        let mut battle = Battle::new(army, BattleRng::new(7));
+        let mut outbox = Outbox::new(tx);
+        let mut ui = ArmySnapshot::default();
+        let mut merged = 0;
+        let mut seq = 0;
+        outbox.push(BattleOutput::ArmySnapshot(ArmySnapshot::from_army(
+            battle.army(),
+        )));
+
+        let mut step = 0;
+        while let Some(events) = battle.step() {
+            step += 1;
+            // 援军：新建一列、加入已有列
+            if step % 50 == 0
+                && let Some(unit) = reinforcements.pop_front()
+            {
+                let col = if step % 100 == 0 { 0 } else { usize::MAX };
+                battle.reinforce(Faction::Ally, col, unit);
+            }
+            outbox.extend(events.into_iter().map(BattleOutput::BattleEvent));
+            let changes = battle.take_changes();
+            if !changes.is_empty() {
+                seq += 1;
+                outbox.push(BattleOutput::ArmyDiff(ArmyDiff {
+                    from: seq - 1,
+                    seq,
+                    changes,
+                }));
+            }
+            assert!(outbox.flush());
+            if step % receive_every == 0 {
+                merged += receive(&rx, &mut ui);
+            }
+        }
+        while outbox.backlog() > 0 {
+            assert!(outbox.flush());
+            merged += receive(&rx, &mut ui);
+        }
+        assert!(reinforcements.is_empty());
+        let expected = ArmySnapshot {
+            seq,
+            ..ArmySnapshot::from_army(battle.army())
+        };
+        (ui, expected, merged)
+    }
+
+    #[test]
+    fn diffs_rebuild_final_army() {
+        let (tx, rx) = flume::unbounded();
+        let (ui, expected, merged) = replay(tx, rx, 1);
+        assert_eq!(merged, 0);
+        assert_eq!(ui, expected);
+    }
+
+    #[test]
+    fn coalesced_diffs_rebuild_final_army() {
+        let (tx, rx) = flume::bounded(1);
+        let (ui, expected, merged) = replay(tx, rx, 7);
+        assert!(merged > 0);
+        assert_eq!(ui, expected);
+    }
+}
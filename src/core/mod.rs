@@ -1 +1,7 @@
 pub mod batttle;
+pub mod bonus;
+pub mod delivery;
+pub mod resource;
+pub mod rng;
+#[cfg(feature = "scripting")]
+pub mod script;
@@ -0,0 +1,265 @@
+// 挂机资源：按各自周期增长，离线期间的收益在启动时补发
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use flume::Sender;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    core::bonus::{BonusManager, BonusTarget},
+    global::global_tokio_runtime,
+    utils,
+};
+
+// 资源调度最长睡眠时间，保证运行中注册的新资源能及时开始计时
+const MAX_SLEEP_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DQWMResource {
+    pub id: u32,             // 唯一标识符
+    pub name: String,        // 资源名称
+    pub description: String, // 资源描述
+    pub value: u128,         // 资源值
+    pub max: u128,           // 资源最大值
+    pub base_change: u128,   // 每个周期的基础变化值
+    pub change: u128,        // 每个周期的变化值（叠加加成后）
+    pub interval: u64,       // 资源刷新间隔（毫秒），0 表示不刷新，1000 代表 1 秒更新一次
+    pub last_update_at: u64, // 最后一次结算时间（unix 毫秒）
+}
+impl DQWMResource {
+    // 设置资源值，不超过上限
+    #[inline]
+    pub fn set_value(&mut self, value: u128) {
+        self.value = value.min(self.max);
+    }
+
+    // 增加资源，返回实际增加量
+    #[inline]
+    pub fn add(&mut self, amount: u128) -> u128 {
+        let before = self.value;
+        self.set_value(self.value.saturating_add(amount));
+        self.value - before
+    }
+
+    // 下一次到期时间
+    #[inline]
+    pub fn next_tick(&self) -> Option<u64> {
+        (self.interval > 0).then(|| self.last_update_at + self.interval)
+    }
+
+    // 结算到 now 为止所有到期的周期，返回实际增加量
+    // 不足一个周期的部分保留到下次，计时不会因调度抖动而漂移
+    pub fn catch_up(&mut self, now: u64) -> u128 {
+        if self.interval == 0 || now <= self.last_update_at {
+            return 0;
+        }
+        let ticks = (now - self.last_update_at) / self.interval;
+        self.last_update_at += ticks * self.interval;
+        self.add(self.change.saturating_mul(ticks as u128))
+    }
+}
+
+// 存档中的资源：只有玩家数据，上限、产量、名称等以当前资源定义为准
+// 字段与 DQWMResource 同名，旧存档里的完整资源也能读取（多余字段忽略）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceState {
+    pub id: u32,
+    pub value: u128,
+    pub last_update_at: u64,
+}
+
+// 发给界面的资源消息
+#[derive(Debug)]
+pub enum ResourceOutput {
+    Snapshot(Vec<DQWMResource>), // 全量
+    Updated(Vec<DQWMResource>),  // 本次变化的资源
+    Offline(OfflineEarnings),    // 离线收益
+}
+
+// 离线收益
+#[derive(Debug, Clone, Default)]
+pub struct OfflineEarnings {
+    pub elapsed_ms: u64,         // 离线时长：距上次在前台（或存档）的时间
+    pub gains: Vec<(u32, u128)>, // 资源 id -> 增加量
+}
+
+#[derive(Debug, Default)]
+pub struct DQWMResourceMgr {
+    resources: BTreeMap<u32, DQWMResource>, // 资源列表
+}
+impl DQWMResourceMgr {
+    // 注册资源，id 相同则覆盖
+    pub fn register(&mut self, resource: DQWMResource) {
+        self.resources.insert(resource.id, resource);
+    }
+
+    pub fn get(&self, id: u32) -> Option<&DQWMResource> {
+        self.resources.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut DQWMResource> {
+        self.resources.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DQWMResource> {
+        self.resources.values()
+    }
+
+    // 加成变化后重算产量
+    pub fn recompute(&mut self, bonuses: &BonusManager) {
+        for r in self.resources.values_mut() {
+            r.change = bonuses.apply_u128(BonusTarget::ResourceChange(r.id), r.base_change);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<DQWMResource> {
+        self.resources.values().cloned().collect()
+    }
+
+    // 需要存档的玩家数据
+    pub fn states(&self) -> Vec<ResourceState> {
+        self.resources
+            .values()
+            .map(|r| ResourceState {
+                id: r.id,
+                value: r.value,
+                last_update_at: r.last_update_at,
+            })
+            .collect()
+    }
+
+    // 把存档中的玩家数据合并到已注册的资源上，已不存在的资源丢弃
+    pub fn restore(&mut self, states: impl IntoIterator<Item = ResourceState>) {
+        for state in states {
+            match self.resources.get_mut(&state.id) {
+                Some(r) => {
+                    r.set_value(state.value);
+                    r.last_update_at = state.last_update_at;
+                }
+                None => log::warn!("存档中的资源 {} 已不存在，丢弃", state.id),
+            }
+        }
+    }
+
+    // 最近一次到期时间
+    pub fn next_due(&self) -> Option<u64> {
+        self.resources
+            .values()
+            .filter_map(DQWMResource::next_tick)
+            .min()
+    }
+
+    // 结算所有到期资源，返回有变化的资源
+    pub fn tick(&mut self, now: u64) -> Vec<DQWMResource> {
+        self.resources
+            .values_mut()
+            .filter(|r| r.next_tick().is_some_and(|at| at <= now))
+            .filter_map(|r| (r.catch_up(now) > 0).then(|| r.clone()))
+            .collect()
+    }
+
+    // 补发离线收益（启动及恢复前台时调用），返回每个资源的增加量
+    // last_seen 为上次在前台（或存档）的时间，只用于计算离线时长
+    pub fn catch_up(&mut self, now: u64, last_seen: u64) -> OfflineEarnings {
+        let elapsed_ms = now.saturating_sub(last_seen);
+        let gains = self
+            .resources
+            .values_mut()
+            .filter_map(|r| {
+                let gained = r.catch_up(now);
+                (gained > 0).then_some((r.id, gained))
+            })
+            .collect();
+        OfflineEarnings { elapsed_ms, gains }
+    }
+}
+
+// 默认资源
+pub fn default_resources(now: u64) -> Vec<DQWMResource> {
+    vec![
+        DQWMResource {
+            id: 1,
+            name: "Gold".to_string(),
+            description: "Basic currency".to_string(),
+            value: 0,
+            max: 1_000_000,
+            base_change: 1,
+            change: 1,
+            interval: 1000,
+            last_update_at: now,
+        },
+        DQWMResource {
+            id: 2,
+            name: "Food".to_string(),
+            description: "Feeds the army".to_string(),
+            value: 0,
+            max: 10_000,
+            base_change: 5,
+            change: 5,
+            interval: 5000,
+            last_update_at: now,
+        },
+    ]
+}
+
+// 在全局运行时上驱动资源增长，把变化发给界面；界面关闭通道后退出
+// 启动时先根据 last_update_at 补发离线收益，last_seen 为存档时间
+pub fn spawn(
+    mgr: Arc<Mutex<DQWMResourceMgr>>,
+    tx: Sender<ResourceOutput>,
+    last_seen: u64,
+) -> JoinHandle<()> {
+    global_tokio_runtime().spawn(async move {
+        let offline = lock(&mgr).catch_up(utils::now_ms() as u64, last_seen);
+        if !offline.gains.is_empty() {
+            log::info!(
+                "离线 {} 毫秒，补发收益 {:?}",
+                offline.elapsed_ms,
+                offline.gains
+            );
+            if tx
+                .send_async(ResourceOutput::Offline(offline))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        let snapshot = lock(&mgr).snapshot();
+        if tx
+            .send_async(ResourceOutput::Snapshot(snapshot))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        loop {
+            let now = utils::now_ms() as u64;
+            let sleep_ms = lock(&mgr)
+                .next_due()
+                .map_or(MAX_SLEEP_MS, |at| at.saturating_sub(now).min(MAX_SLEEP_MS));
+            tokio::time::sleep(tokio::time::Duration::from_millis(sleep_ms)).await;
+
+            let updated = lock(&mgr).tick(utils::now_ms() as u64);
+            if updated.is_empty() {
+                continue;
+            }
+            if tx
+                .send_async(ResourceOutput::Updated(updated))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    })
+}
+
+// 锁被污染时仍然取出数据，资源数据本身不会处于半更新状态
+pub fn lock(mgr: &Mutex<DQWMResourceMgr>) -> std::sync::MutexGuard<'_, DQWMResourceMgr> {
+    mgr.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,66 @@
+// 战斗用确定性随机数（SplitMix64）
+// 不依赖外部 crate，保证同一种子在任何平台、任何版本下得到完全相同的序列，
+// 用于战斗回放和数值平衡。
+
+#[derive(Debug, Clone)]
+pub struct BattleRng {
+    seed: u64,
+    state: u64,
+}
+impl BattleRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    // 初始种子（用于复现）
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // [0, 1) 区间的浮点数
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    // [0, n) 区间的整数，n 为 0 时返回 0
+    pub fn gen_index(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (((self.next_u64() as u128) * (n as u128)) >> 64) as usize
+    }
+
+    // 按概率判定（暴击、闪避、命中等），rate 取值 0.0 ~ 1.0
+    pub fn chance(&mut self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+        if rate >= 1.0 {
+            return true;
+        }
+        self.next_f64() < rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 固定输出：改动算法会让已有战斗无法复现，必须在这里显式更新
+    // 种子 0 的前两个值与 SplitMix64 参考实现一致
+    #[test]
+    fn sequence_is_pinned() {
+        let mut rng = BattleRng::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+        assert_eq!(BattleRng::new(42).next_u64(), 0xBDD7_3226_2FEB_6E95);
+    }
+}
@@ -0,0 +1,298 @@
+// 策划公式脚本（rhai）：公式只编译一次并缓存 AST，每次计算只替换变量
+// 例：if lv > 10 { #{"1001": 5*lv, "1005": 50*lv} } else { #{"1001": 2*lv, "1005": 20*lv} }
+
+use std::{collections::HashMap, fmt};
+
+use rhai::{AST, Dynamic, Engine, Map, Scope};
+
+// 单条公式最多执行的操作数，防止死循环卡住游戏
+const MAX_OPERATIONS: u64 = 100_000;
+
+// 公式种类，同一种类的公式使用同一组变量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormulaKind {
+    UpgradeCost,   // 升级消耗
+    ResourceYield, // 资源产量
+    Damage,        // 伤害
+    Bonus,         // 加成数值
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    NotFound(FormulaKind, String),           // 公式不存在
+    Compile(FormulaKind, String, String),    // 编译失败：名称、原因
+    Eval(FormulaKind, String, String),       // 运行失败：名称、原因
+    Type(FormulaKind, String, &'static str), // 返回值类型不符：名称、期望类型
+}
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::NotFound(kind, name) => write!(f, "公式 {:?}/{} 不存在", kind, name),
+            ScriptError::Compile(kind, name, e) => {
+                write!(f, "公式 {:?}/{} 编译失败: {}", kind, name, e)
+            }
+            ScriptError::Eval(kind, name, e) => {
+                write!(f, "公式 {:?}/{} 运行失败: {}", kind, name, e)
+            }
+            ScriptError::Type(kind, name, expected) => {
+                write!(f, "公式 {:?}/{} 返回值应为 {}", kind, name, expected)
+            }
+        }
+    }
+}
+impl std::error::Error for ScriptError {}
+
+// 公式变量：每种公式对应一个结构体，保证传入的变量名和类型固定
+pub trait FormulaArgs {
+    const KIND: FormulaKind;
+    fn push(&self, scope: &mut Scope);
+}
+
+// 升级消耗：lv
+#[derive(Debug, Clone, Copy)]
+pub struct UpgradeCostArgs {
+    pub lv: i64,
+}
+impl FormulaArgs for UpgradeCostArgs {
+    const KIND: FormulaKind = FormulaKind::UpgradeCost;
+    fn push(&self, scope: &mut Scope) {
+        scope.push("lv", self.lv);
+    }
+}
+
+// 资源产量：lv、base（基础产量）
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceYieldArgs {
+    pub lv: i64,
+    pub base: i64,
+}
+impl FormulaArgs for ResourceYieldArgs {
+    const KIND: FormulaKind = FormulaKind::ResourceYield;
+    fn push(&self, scope: &mut Scope) {
+        scope.push("lv", self.lv);
+        scope.push("base", self.base);
+    }
+}
+
+// 伤害：atk、def、crit_damage
+#[derive(Debug, Clone, Copy)]
+pub struct DamageArgs {
+    pub atk: i64,
+    pub def: i64,
+    pub crit_damage: f64,
+}
+impl FormulaArgs for DamageArgs {
+    const KIND: FormulaKind = FormulaKind::Damage;
+    fn push(&self, scope: &mut Scope) {
+        scope.push("atk", self.atk);
+        scope.push("def", self.def);
+        scope.push("crit_damage", self.crit_damage);
+    }
+}
+
+// 加成数值：lv
+#[derive(Debug, Clone, Copy)]
+pub struct BonusArgs {
+    pub lv: i64,
+}
+impl FormulaArgs for BonusArgs {
+    const KIND: FormulaKind = FormulaKind::Bonus;
+    fn push(&self, scope: &mut Scope) {
+        scope.push("lv", self.lv);
+    }
+}
+
+// 公式库：编译后的 AST 缓存
+pub struct Formulas {
+    engine: Engine,
+    asts: HashMap<(FormulaKind, String), AST>,
+}
+impl Default for Formulas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Formulas {
+    pub fn new() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        Self {
+            engine,
+            asts: HashMap::new(),
+        }
+    }
+
+    // 编译并缓存公式，同名覆盖
+    pub fn compile(
+        &mut self,
+        kind: FormulaKind,
+        name: &str,
+        source: &str,
+    ) -> Result<(), ScriptError> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| ScriptError::Compile(kind, name.to_string(), e.to_string()))?;
+        self.asts.insert((kind, name.to_string()), ast);
+        Ok(())
+    }
+
+    // 批量编译，返回所有失败的公式
+    pub fn compile_all<'a>(
+        &mut self,
+        sources: impl IntoIterator<Item = (FormulaKind, &'a str, &'a str)>,
+    ) -> Vec<ScriptError> {
+        sources
+            .into_iter()
+            .filter_map(|(kind, name, source)| self.compile(kind, name, source).err())
+            .collect()
+    }
+
+    pub fn contains(&self, kind: FormulaKind, name: &str) -> bool {
+        self.asts.contains_key(&(kind, name.to_string()))
+    }
+
+    fn eval_dynamic<A: FormulaArgs>(&self, name: &str, args: &A) -> Result<Dynamic, ScriptError> {
+        let ast = self
+            .asts
+            .get(&(A::KIND, name.to_string()))
+            .ok_or_else(|| ScriptError::NotFound(A::KIND, name.to_string()))?;
+        let mut scope = Scope::new();
+        args.push(&mut scope);
+        self.engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+            .map_err(|e| ScriptError::Eval(A::KIND, name.to_string(), e.to_string()))
+    }
+
+    // 整数结果（升级消耗、产量等），浮点结果向下取整
+    pub fn eval_int<A: FormulaArgs>(&self, name: &str, args: &A) -> Result<i64, ScriptError> {
+        let value = self.eval_dynamic(name, args)?;
+        value
+            .as_int()
+            .or_else(|_| value.as_float().map(|f| f as i64))
+            .map_err(|_| ScriptError::Type(A::KIND, name.to_string(), "整数"))
+    }
+
+    // 浮点结果（比例、倍率等），整数结果自动转换
+    pub fn eval_float<A: FormulaArgs>(&self, name: &str, args: &A) -> Result<f64, ScriptError> {
+        let value = self.eval_dynamic(name, args)?;
+        value
+            .as_float()
+            .or_else(|_| value.as_int().map(|i| i as f64))
+            .map_err(|_| ScriptError::Type(A::KIND, name.to_string(), "浮点数"))
+    }
+
+    // 对象结果，如 #{"1001": 5*lv}：key 为目标 id，value 为数值
+    pub fn eval_map<A: FormulaArgs>(
+        &self,
+        name: &str,
+        args: &A,
+    ) -> Result<Vec<(String, f64)>, ScriptError> {
+        let err = || ScriptError::Type(A::KIND, name.to_string(), "对象 #{id: 数值}");
+        let map = self
+            .eval_dynamic(name, args)?
+            .try_cast::<Map>()
+            .ok_or_else(err)?;
+        map.into_iter()
+            .map(|(k, v)| {
+                let n = v
+                    .as_float()
+                    .or_else(|_| v.as_int().map(|i| i as f64))
+                    .map_err(|_| err())?;
+                Ok((k.to_string(), n))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formulas(kind: FormulaKind, name: &str, source: &str) -> Formulas {
+        let mut formulas = Formulas::new();
+        formulas
+            .compile(kind, name, source)
+            .expect("测试公式编译失败");
+        formulas
+    }
+
+    #[test]
+    fn eval_map_by_level() {
+        let f = formulas(
+            FormulaKind::Bonus,
+            "atk",
+            r#"if lv > 10 { #{"1001": 5*lv} }"#,
+        );
+        assert_eq!(
+            f.eval_map("atk", &BonusArgs { lv: 12 }),
+            Ok(vec![("1001".to_string(), 60.0)])
+        );
+        // 条件不成立时没有返回对象
+        assert!(matches!(
+            f.eval_map("atk", &BonusArgs { lv: 5 }),
+            Err(ScriptError::Type(FormulaKind::Bonus, _, _))
+        ));
+    }
+
+    #[test]
+    fn compile_error() {
+        let mut f = Formulas::new();
+        assert!(matches!(
+            f.compile(FormulaKind::UpgradeCost, "bad", "lv * (2 +"),
+            Err(ScriptError::Compile(FormulaKind::UpgradeCost, _, _))
+        ));
+        assert!(!f.contains(FormulaKind::UpgradeCost, "bad"));
+    }
+
+    #[test]
+    fn division_by_zero_is_eval_error() {
+        let f = formulas(FormulaKind::UpgradeCost, "div", "1 / 0");
+        assert!(matches!(
+            f.eval_int("div", &UpgradeCostArgs { lv: 1 }),
+            Err(ScriptError::Eval(FormulaKind::UpgradeCost, _, _))
+        ));
+    }
+
+    #[test]
+    fn runaway_loop_hits_operation_limit() {
+        let f = formulas(FormulaKind::UpgradeCost, "loop", "loop {}");
+        let Err(ScriptError::Eval(_, _, reason)) = f.eval_int("loop", &UpgradeCostArgs { lv: 1 })
+        else {
+            panic!("死循环应返回运行失败");
+        };
+        assert!(reason.contains("operations"), "{}", reason);
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let f = formulas(FormulaKind::Damage, "text", r#""atk""#);
+        let args = DamageArgs {
+            atk: 10,
+            def: 2,
+            crit_damage: 1.5,
+        };
+        assert!(matches!(
+            f.eval_float("text", &args),
+            Err(ScriptError::Type(FormulaKind::Damage, _, _))
+        ));
+    }
+
+    #[test]
+    fn missing_formula() {
+        let f = Formulas::new();
+        assert_eq!(
+            f.eval_int("cost", &UpgradeCostArgs { lv: 1 }),
+            Err(ScriptError::NotFound(
+                FormulaKind::UpgradeCost,
+                "cost".to_string()
+            ))
+        );
+        // 同名但种类不同的公式不算
+        let f = formulas(FormulaKind::Bonus, "cost", "lv");
+        assert!(matches!(
+            f.eval_int("cost", &UpgradeCostArgs { lv: 1 }),
+            Err(ScriptError::NotFound(..))
+        ));
+    }
+}
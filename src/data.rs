@@ -0,0 +1,191 @@
+// 策划配表加载：内置于安装包，桌面端可用外部目录覆盖，改数值不用重新编译
+
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+use crate::model::{BaseUnitRegistry, BaseUnitTemp};
+
+// 内置配表
+const BUNDLED_UNITS: &str = include_str!("../assets/data/units.json");
+// 配表文件名（覆盖目录下同名文件优先）
+const UNITS_FILE: &str = "units.json";
+
+// 配表中的一行错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub row: usize, // 行号（从 0 开始，对应数组下标）
+    pub id: u32,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(PathBuf, std::io::Error), // 读取文件失败
+    Parse(serde_json::Error),    // 格式错误
+    Invalid(Vec<RowError>),      // 数据校验失败，列出所有错误行
+}
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Io(path, e) => write!(f, "读取配表 {} 失败: {}", path.display(), e),
+            DataError::Parse(e) => write!(f, "配表格式错误: {}", e),
+            DataError::Invalid(rows) => {
+                write!(f, "配表校验失败，共 {} 处错误:", rows.len())?;
+                for r in rows {
+                    write!(f, "\n  第 {} 行 (id={}): {}", r.row, r.id, r.reason)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+impl std::error::Error for DataError {}
+
+// 解析并校验单位配表
+pub fn parse_base_units(text: &str) -> Result<BaseUnitRegistry, DataError> {
+    let temps: Vec<BaseUnitTemp> = serde_json::from_str(text).map_err(DataError::Parse)?;
+    let errors = validate_base_units(&temps);
+    if !errors.is_empty() {
+        return Err(DataError::Invalid(errors));
+    }
+    Ok(BaseUnitRegistry::new(temps))
+}
+
+// 校验：重复 id、生命为 0、攻击间隔为 0、名字为空、负数比例、概率大于 1、
+// 命中率为 0、闪避率为 1、生命偷取不小于 1
+pub fn validate_base_units(temps: &[BaseUnitTemp]) -> Vec<RowError> {
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+    for (row, t) in temps.iter().enumerate() {
+        let mut push = |reason: String| {
+            errors.push(RowError {
+                row,
+                id: t.id,
+                reason,
+            })
+        };
+        if !ids.insert(t.id) {
+            push(format!("id {} 重复", t.id));
+        }
+        if t.name.trim().is_empty() {
+            push("name 不能为空".to_string());
+        }
+        if t.base_hp == 0 {
+            push("base_hp（max_hp）不能为 0".to_string());
+        }
+        if t.base_speek == 0 {
+            push("base_speek 不能为 0".to_string());
+        }
+        for (field, value) in [
+            ("base_crit_rate", t.base_crit_rate),
+            ("base_crit_damage", t.base_crit_damage),
+            ("base_hit_rate", t.base_hit_rate),
+            ("base_dodge_rate", t.base_dodge_rate),
+            ("base_attack_speed", t.base_attack_speed),
+            ("base_life_steal", t.base_life_steal),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                push(format!("{} 必须为非负数: {}", field, value));
+            }
+        }
+        for (field, value) in [
+            ("base_crit_rate", t.base_crit_rate),
+            ("base_hit_rate", t.base_hit_rate),
+            ("base_dodge_rate", t.base_dodge_rate),
+        ] {
+            if value > 1.0 {
+                push(format!("{} 是概率，不能大于 1: {}", field, value));
+            }
+        }
+        // 以下数值会让战斗分不出胜负
+        if t.base_hit_rate == 0.0 {
+            push("base_hit_rate 不能为 0，永远不会命中".to_string());
+        }
+        if t.base_dodge_rate == 1.0 {
+            push("base_dodge_rate 必须小于 1，永远不会被命中".to_string());
+        }
+        if t.base_life_steal >= 1.0 {
+            push(format!(
+                "base_life_steal 必须小于 1，否则伤害全额回血永远打不死: {}",
+                t.base_life_steal
+            ));
+        }
+    }
+    errors
+}
+
+// 桌面端覆盖目录：环境变量 DQWM_DATA_DIR，否则为程序所在目录下的 data
+#[cfg(not(target_os = "android"))]
+pub fn override_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("DQWM_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.join("data")))
+}
+#[cfg(target_os = "android")]
+pub fn override_dir() -> Option<PathBuf> {
+    None
+}
+
+// 加载单位配表：覆盖目录有文件则用覆盖文件，否则用内置配表
+pub fn load_base_units() -> Result<BaseUnitRegistry, DataError> {
+    if let Some(path) = override_dir().map(|d| d.join(UNITS_FILE))
+        && path.is_file()
+    {
+        log::info!("使用外部单位配表: {}", path.display());
+        let text = std::fs::read_to_string(&path).map_err(|e| DataError::Io(path, e))?;
+        return parse_base_units(&text);
+    }
+    parse_base_units(BUNDLED_UNITS)
+}
+
+// 加载失败时记录日志并回退到内置配表，保证游戏可以启动
+pub fn load_base_units_or_bundled() -> BaseUnitRegistry {
+    load_base_units()
+        .or_else(|e| {
+            log::error!("{}，回退到内置配表", e);
+            parse_base_units(BUNDLED_UNITS)
+        })
+        .unwrap_or_else(|e| {
+            log::error!("内置配表错误: {}", e);
+            BaseUnitRegistry::default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_units_are_valid() {
+        assert!(parse_base_units(BUNDLED_UNITS).is_ok_and(|r| !r.is_empty()));
+    }
+
+    #[test]
+    fn rejects_rates_that_stall_battles() {
+        let row = |id: u32, hit: f64, dodge: f64, crit: f64, life_steal: f64| {
+            format!(
+                r#"{{"id": {id}, "name": "u{id}", "base_atk": 10, "base_hp": 100, "base_def": 0,
+                "base_speek": 1000, "base_crit_rate": {crit}, "base_crit_damage": 1.5,
+                "base_hit_rate": {hit}, "base_dodge_rate": {dodge}, "base_attack_speed": 1.0,
+                "base_life_steal": {life_steal}}}"#
+            )
+        };
+        let text = format!(
+            "[{}]",
+            [
+                row(1, 0.9, 0.1, 0.1, 0.2), // 正常
+                row(2, 0.0, 0.1, 0.1, 0.2), // 命中率为 0
+                row(3, 1.5, 0.1, 0.1, 0.2), // 命中率大于 1
+                row(4, 0.9, 1.0, 0.1, 0.2), // 闪避率为 1
+                row(5, 0.9, 0.1, 1.2, 0.2), // 暴击率大于 1
+                row(6, 0.9, 0.1, 0.1, 1.0), // 生命偷取为 1
+            ]
+            .join(",")
+        );
+        let temps: Vec<BaseUnitTemp> = serde_json::from_str(&text).expect("测试配表格式错误");
+        let ids: Vec<u32> = validate_base_units(&temps).iter().map(|e| e.id).collect();
+        assert_eq!(ids, [2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,138 @@
+// 调试信息：帧时间分位数、战斗事件数、通道积压、纹理缓存、tokio 任务数
+// 由设置项控制显示，调试版可用 F3 或三指触摸切换；正式版默认关闭
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use egui::{
+    Align2, Color32, Context, FontId, Id, LayerId, Modifiers, Order, Plugin, TextureHandle, pos2,
+    vec2,
+};
+
+use crate::{
+    Key, R,
+    global::{CONFIG, global_tokio_runtime},
+    settings::{self, Settings},
+};
+
+const FRAME_WINDOW: usize = 240; // 统计最近多少帧
+const MAX_FRAME_TIME: Duration = Duration::from_secs(1); // 超过视为挂起恢复，不计入
+
+// 界面每帧发布的统计，存放在 ctx data 的 Key::Debug 下
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugStats {
+    pub events: usize,         // 事件历史条数
+    pub anims: usize,          // 正在播放的动画数
+    pub battle_backlog: usize, // 本帧处理前战斗通道积压的消息数
+}
+
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    touching: bool, // 上一帧是否三指按下，用于识别按下的一刻
+}
+impl DebugOverlay {
+    // F3 或三指按下
+    fn toggled(&mut self, ctx: &Context) -> bool {
+        let key = ctx.input_mut(|i| i.consume_key(Modifiers::NONE, egui::Key::F3));
+        let touching = ctx.input(|i| i.multi_touch().is_some_and(|t| t.num_touches >= 3));
+        let gesture = touching && !self.touching;
+        self.touching = touching;
+        key || gesture
+    }
+
+    // 帧时间分位数（毫秒）：p50、p95、p99、最大
+    fn percentiles(&self) -> Option<[f32; 4]> {
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort_unstable();
+        let max = *sorted.last()?;
+        let at = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+        Some([at(0.5), at(0.95), at(0.99), max].map(|d| d.as_secs_f32() * 1000.0))
+    }
+}
+
+impl Plugin for DebugOverlay {
+    fn debug_name(&self) -> &'static str {
+        "DebugOverlay"
+    }
+
+    fn on_begin_pass(&mut self, ctx: &Context) {
+        // 帧时间一直统计，打开时立即有数据
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            let dt = now - last;
+            if dt < MAX_FRAME_TIME {
+                if self.frame_times.len() >= FRAME_WINDOW {
+                    self.frame_times.pop_front();
+                }
+                self.frame_times.push_back(dt);
+            }
+        }
+
+        if cfg!(debug_assertions) && self.toggled(ctx) {
+            let current = CONFIG.settings();
+            settings::update(Settings {
+                show_fps: !current.show_fps,
+                ..current
+            });
+        }
+    }
+
+    // 在界面绘制之后画，读到的是本帧统计
+    fn on_end_pass(&mut self, ctx: &Context) {
+        if !CONFIG.show_fps.load(Ordering::Relaxed) {
+            return;
+        }
+        let stats = ctx
+            .data(|d| d.get_temp::<DebugStats>(Id::new(Key::Debug)))
+            .unwrap_or_default();
+        let (textures, texture_bytes) = ctx
+            .data(|d| d.get_temp::<HashMap<R, TextureHandle>>(Id::new(Key::Resource)))
+            .map_or((0, 0), |map| {
+                let bytes = map.values().map(|t| t.byte_size()).sum::<usize>();
+                (map.len(), bytes)
+            });
+        let tasks = global_tokio_runtime().metrics().num_alive_tasks();
+
+        let total: Duration = self.frame_times.iter().sum();
+        let fps = if total.is_zero() {
+            0.0
+        } else {
+            self.frame_times.len() as f32 / total.as_secs_f32()
+        };
+        let mut lines = vec![format!("FPS {:.1}", fps)];
+        if let Some([p50, p95, p99, max]) = self.percentiles() {
+            lines.push(format!(
+                "frame ms p50 {:.1} p95 {:.1} p99 {:.1} max {:.1}",
+                p50, p95, p99, max
+            ));
+        }
+        lines.push(format!(
+            "events {}  anims {}  backlog {}",
+            stats.events, stats.anims, stats.battle_backlog
+        ));
+        lines.push(format!(
+            "textures {} ({} KB)",
+            textures,
+            texture_bytes / 1024
+        ));
+        lines.push(format!("tokio tasks {}", tasks));
+
+        let painter = ctx.layer_painter(LayerId::new(Order::Debug, Id::new("debug_overlay")));
+        let font = FontId::monospace(14.0);
+        let galley = painter.layout_no_wrap(lines.join("\n"), font, Color32::from_rgb(0, 255, 0));
+        let rect = Align2::LEFT_TOP
+            .anchor_size(pos2(10.0, 10.0), galley.size())
+            .expand(4.0);
+        painter.rect_filled(rect, 2.0, Color32::from_black_alpha(160));
+        painter.galley(
+            rect.min + vec2(4.0, 4.0),
+            galley,
+            Color32::from_rgb(0, 255, 0),
+        );
+    }
+}
@@ -1,6 +1,13 @@
-use std::sync::{LazyLock, OnceLock, atomic::AtomicUsize};
+use std::sync::{
+    LazyLock, Mutex, OnceLock, PoisonError, RwLock,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use flume::{Receiver, Sender};
 use tokio::runtime::Runtime;
 
+use crate::settings::Settings;
+
 pub fn global_tokio_runtime() -> &'static Runtime {
     static RUNTIME: OnceLock<Runtime> = OnceLock::new();
     RUNTIME.get_or_init(|| {
@@ -12,10 +19,55 @@ pub fn global_tokio_runtime() -> &'static Runtime {
 }
 
 pub struct GlobalConfig {
-    pub max_enemy_cols: AtomicUsize, // 战斗页面敌方最大列数
-    pub max_ally_cols: AtomicUsize,  // 战斗页面我方最大列数
+    pub max_enemy_cols: AtomicUsize,           // 战斗页面敌方最大列数
+    pub max_ally_cols: AtomicUsize,            // 战斗页面我方最大列数
+    pub show_fps: AtomicBool,                  // 显示调试信息
+    settings: RwLock<Settings>,                // 完整设置，常用项另存原子量便于每帧读取
+    subscribers: Mutex<Vec<Sender<Settings>>>, // 设置变化的订阅者
+}
+impl GlobalConfig {
+    fn new(settings: Settings) -> Self {
+        Self {
+            max_enemy_cols: AtomicUsize::new(settings.max_enemy_cols),
+            max_ally_cols: AtomicUsize::new(settings.max_ally_cols),
+            show_fps: AtomicBool::new(settings.show_fps),
+            settings: RwLock::new(settings),
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    // 当前设置快照
+    pub fn settings(&self) -> Settings {
+        *self.settings.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // 应用新设置并通知订阅者，已断开的订阅者移除
+    pub fn apply(&self, settings: Settings) {
+        let settings = settings.sanitized();
+        self.max_enemy_cols
+            .store(settings.max_enemy_cols, Ordering::Relaxed);
+        self.max_ally_cols
+            .store(settings.max_ally_cols, Ordering::Relaxed);
+        self.show_fps.store(settings.show_fps, Ordering::Relaxed);
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = settings;
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|tx| tx.send(settings).is_ok());
+    }
+
+    // 订阅设置变化，每次 apply 收到一份新设置
+    pub fn subscribe(&self) -> Receiver<Settings> {
+        let (tx, rx) = flume::unbounded();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(tx);
+        rx
+    }
 }
-pub static CONFIG: LazyLock<GlobalConfig> = LazyLock::new(|| GlobalConfig {
-    max_enemy_cols: AtomicUsize::new(10),
-    max_ally_cols: AtomicUsize::new(10),
-});
+pub static CONFIG: LazyLock<GlobalConfig> =
+    LazyLock::new(|| GlobalConfig::new(Settings::default()));
@@ -0,0 +1,107 @@
+// 界面文字：以英文为 key，选择中文且中文字体已加载时查表翻译
+// 内置字体没有中文字形，中文字体从系统目录加载，找不到时仍显示英文
+
+use std::{
+    fs,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use egui::{
+    Context, FontData, FontFamily,
+    epaint::text::{FontInsert, FontPriority, InsertFontFamily},
+};
+
+use crate::{global::CONFIG, settings::Language};
+
+static CJK_FONT: AtomicBool = AtomicBool::new(false); // 中文字体是否已加载
+
+// 各平台常见的中文字体
+const CJK_FONT_PATHS: [&str; 9] = [
+    "/system/fonts/NotoSansCJK-Regular.ttc",
+    "/system/fonts/NotoSansSC-Regular.otf",
+    "/system/fonts/DroidSansFallback.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+];
+
+// 加载系统中文字体作为后备字体，只加载一次；返回是否可用
+pub fn load_cjk_font(ctx: &Context) -> bool {
+    if CJK_FONT.load(Ordering::Relaxed) {
+        return true;
+    }
+    let Some((path, bytes)) = CJK_FONT_PATHS
+        .iter()
+        .find_map(|p| fs::read(p).ok().map(|b| (p, b)))
+    else {
+        log::warn!("没有找到中文字体，界面保持英文");
+        return false;
+    };
+    log::info!("加载中文字体 {}", path);
+    let families = [FontFamily::Proportional, FontFamily::Monospace]
+        .into_iter()
+        .map(|family| InsertFontFamily {
+            family,
+            priority: FontPriority::Lowest,
+        })
+        .collect();
+    ctx.add_font(FontInsert::new(
+        "cjk",
+        FontData::from_owned(bytes),
+        families,
+    ));
+    CJK_FONT.store(true, Ordering::Relaxed);
+    true
+}
+
+pub fn tr(text: &'static str) -> &'static str {
+    if CONFIG.settings().language != Language::Chinese || !CJK_FONT.load(Ordering::Relaxed) {
+        return text;
+    }
+    match text {
+        "DQWM" => crate::APP_NAME,
+        "Battle" => "战斗",
+        "Formation" => "阵型",
+        "Resources" => "资源",
+        "Settings" => "设置",
+        "Won" => "胜",
+        "Lost" => "负",
+        "Offline" => "离线",
+        "VICTORY" => "胜利",
+        "DEFEAT" => "失败",
+        "DRAW" => "平局",
+        "Time" => "用时",
+        "Seed" => "种子",
+        "Alive" => "存活",
+        "Ally" => "我方",
+        "Enemy" => "敌方",
+        "Top damage" => "输出排行",
+        "DMG" => "伤害",
+        "TAKEN" => "承伤",
+        "KILL" => "击杀",
+        "ATK" => "出手",
+        "CRIT" => "暴击",
+        "MISS" => "未命中",
+        "DODGE" => "闪避",
+        "SKIP" => "跳过",
+        "Enemy columns" => "敌方列数",
+        "Ally columns" => "我方列数",
+        "Debug overlay" => "调试信息",
+        "Battle speed" => "战斗速度",
+        "Animations" => "战斗动画",
+        "Language" => "语言",
+        "Theme" => "主题",
+        "On" => "开",
+        "Off" => "关",
+        "Low" => "低",
+        "High" => "高",
+        "English" => "英文",
+        "Chinese" => "中文",
+        "Light" => "浅色",
+        "Dark" => "深色",
+        _ => text,
+    }
+}
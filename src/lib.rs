@@ -1,28 +1,52 @@
-use crate::core::batttle::{ArmySnapshot, BattleEvent, BattleOutput};
-use crate::{components::battle_page, model::Unit};
+use crate::command::Command;
+use crate::components::{anim::AnimScheduler, battle_page::BattleStatus};
+use crate::core::batttle::{
+    Army, ArmySnapshot, BattleContext, BattleExecutor, BattleHandle, BattleOutput, BattleReport,
+    BattleSpeed,
+};
+use crate::core::bonus::{self, Bonus, BonusManager, BonusTarget};
+use crate::core::delivery::{self, EventHistory};
+use crate::core::resource::{self, DQWMResource, DQWMResourceMgr, OfflineEarnings, ResourceOutput};
+use crate::debug_overlay::{DebugOverlay, DebugStats};
+use crate::global::CONFIG;
+use crate::router::{Page, Route, Router};
+use crate::save::{PlayerState, Progress};
+use crate::settings::{Language, Settings};
+use crate::{
+    components::{army_page, battle_page, home_page, resource_page, settings_page},
+    model::{Faction, Formation, Unit, UnitUpgrades},
+};
 
 use eframe::{App, NativeOptions};
 use egui::{
-    Align2, CentralPanel, Color32, Context, FontId, Frame, Id, LayerId, Order, Plugin, Rect,
-    TextureHandle, TextureId, Ui, Vec2, Visuals, pos2,
+    Align2, CentralPanel, Color32, Context, FontId, Frame, Id, Modifiers, Rect, Sense,
+    TextureHandle, TextureId, Ui, UiBuilder, Vec2, pos2, vec2,
 };
-use flume::Receiver;
-use global::global_tokio_runtime;
+use flume::{Receiver, Sender};
 use image::ImageFormat;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     process,
-    sync::Arc,
+    sync::{Arc, Mutex, atomic::Ordering},
     time::{Duration, Instant},
 };
 
+pub mod command;
 pub mod components;
 pub mod core;
+pub mod data;
+pub mod debug_overlay;
 pub mod global;
+pub mod i18n;
 pub mod model;
+pub mod router;
+pub mod save;
+pub mod settings;
 pub mod utils;
 
 pub const APP_NAME: &str = "道起微末";
+// 自动战斗时结算画面停留时间
+const AUTO_BATTLE_DELAY: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum R {
@@ -42,6 +66,7 @@ impl R {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Key {
     Resource,
+    Debug, // 调试信息统计
 }
 
 //**拓展Ui */
@@ -66,73 +91,64 @@ impl UiExt for Ui {
         self.painter().rect_filled(rect, 0.0, color);
     }
     fn get_texture_id(&self, r: R) -> TextureId {
-        self.ctx().data(|data| {
-            let mut map = data
-                .get_temp::<HashMap<R, TextureHandle>>(Id::new(Key::Resource))
-                .expect("未初始化资源HashMap!");
-            map.entry(r).or_insert_with(|| r.load(self.ctx())).id()
-        })
-    }
-}
-
-#[derive(Debug)]
-struct FPSPlugin {
-    fps: f32,
-    last_update: Instant,
-    frame_count: u32,
-}
-impl Default for FPSPlugin {
-    fn default() -> Self {
-        Self {
-            fps: 0.0,
-            last_update: Instant::now(),
-            frame_count: 0,
-        }
-    }
-}
-
-impl Plugin for FPSPlugin {
-    fn debug_name(&self) -> &'static str {
-        "FPS"
-    }
-
-    fn on_begin_pass(&mut self, ctx: &Context) {
-        let now = Instant::now();
-        self.frame_count += 1;
-
-        // 每 0.5 秒更新一次 FPS（避免数字跳得太快）
-        if now.duration_since(self.last_update) >= Duration::from_millis(500) {
-            let elapsed_secs = now.duration_since(self.last_update).as_secs_f32();
-            self.fps = self.frame_count as f32 / elapsed_secs;
-            self.frame_count = 0;
-            self.last_update = now;
+        let id = Id::new(Key::Resource);
+        let cached = self.ctx().data(|data| {
+            data.get_temp::<HashMap<R, TextureHandle>>(id)
+                .expect("未初始化资源HashMap!")
+                .get(&r)
+                .map(TextureHandle::id)
+        });
+        if let Some(texture_id) = cached {
+            return texture_id;
         }
-
-        let painter =
-            ctx.layer_painter(LayerId::new(Order::Foreground, egui::Id::new("fps_debug")));
-        painter.text(
-            pos2(10.0, 10.0), // 文本位置
-            Align2::LEFT_TOP, // 对齐方式
-            format!("FPS:{:.1}", self.fps),
-            FontId::monospace(14.0),      // 字体大小
-            Color32::from_rgb(0, 255, 0), // 颜色
-        );
+        // 在锁外加载，加载完写回缓存
+        let texture = r.load(self.ctx());
+        let texture_id = texture.id();
+        self.ctx().data_mut(|data| {
+            data.get_temp_mut_or_default::<HashMap<R, TextureHandle>>(id)
+                .insert(r, texture);
+        });
+        texture_id
     }
 }
 
 pub struct Application {
     battle_rx: Option<Receiver<BattleOutput>>,
+    battle_backlog: usize, // 本帧处理前战斗通道积压的消息数
     current_army: ArmySnapshot,
-    current_event: VecDeque<BattleEvent>,
+    current_event: EventHistory,  // 最近的战斗事件，按序号交给动画调度
+    anims: AnimScheduler,         // 正在播放的战斗动画
+    report: Option<BattleReport>, // 战斗结果，结束后显示结算
+    resource_mgr: Arc<Mutex<DQWMResourceMgr>>, // 资源管理（后台任务驱动）
+    resource_rx: Receiver<ResourceOutput>,
+    bonuses: BonusManager,                       // 加成来源
+    resources: BTreeMap<u32, DQWMResource>,      // 界面侧资源快照
+    offline: Option<(Instant, OfflineEarnings)>, // 本次启动的离线收益，显示几秒
+    unit_upgrades: BTreeMap<u32, UnitUpgrades>,  // 单位养成：BaseUnitTemp.id -> 养成
+    progress: Progress,                          // 战斗进度
+    formation: Formation,                        // 我方阵型，为空时使用默认军队
+    autosave_tx: Sender<PlayerState>,            // 玩家数据变化时发给自动存档任务
+    focused: bool,                               // 窗口是否在前台
+    last_frame: Instant,                         // 上一帧时间，用于发现从后台恢复
+    last_seen_ms: u64,                           // 上次在前台的时间（unix 毫秒），用于计算离线时长
+    battle: Option<BattleHandle>,                // 当前战斗任务句柄
+    auto_battle: bool,                           // 自动战斗
+    finished_at: Option<Instant>,                // 本场战斗结束时间
+    speed: BattleSpeed,                          // 战斗速度
+    resync_pending: bool,                        // 已请求全量快照，等待中
+    router: Router,                              // 页面栈
+    settings_rx: Receiver<Settings>,             // 设置变化通知
 }
 impl Application {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let ctx: &Context = &cc.egui_ctx; // 获取egui上下文
-        ctx.set_visuals(Visuals::light()); // 亮色主题
         egui_extras::install_image_loaders(&cc.egui_ctx); // 注册图像加载器到egui上下文
         load_fonts(ctx, "iconfont", include_bytes!("../assets/fonts/icon.ttf")); // 加载自定义字体
 
-        ctx.add_plugin(FPSPlugin::default());
+        ctx.add_plugin(DebugOverlay::default());
+        // 设置：启动时读取并应用，之后订阅变化
+        CONFIG.apply(settings::load());
+        let settings_rx = CONFIG.subscribe();
         let res: HashMap<R, TextureHandle> = Default::default();
         // 资源
         ctx.data_mut(|w| {
@@ -140,13 +156,395 @@ impl Application {
         });
         // ctx.data(|r| r.get_temp::<u32>(Id::new(1)));
 
-        // let (army_tx, army_rx) = channel::bounded(2);
-        // let (event_tx, event_rx) = channel::bounded(128);
+        // 存档：读取失败时记录日志并以新存档开始
+        // 版本比程序新的存档本次运行不覆盖，损坏的存档已改名保留
+        let saved = save::load().unwrap_or_else(|e| {
+            log::error!("读取存档失败: {}", e);
+            None
+        });
+        let has_save = saved.is_some();
+        let state = saved.unwrap_or_default();
+
+        // 加成：有存档以存档为准
+        let mut bonuses = BonusManager::default();
+        let saved_bonuses = if has_save {
+            state.bonuses
+        } else {
+            bonus::default_bonuses()
+        };
+        for b in saved_bonuses {
+            bonuses.add(b);
+        }
+        // 资源：注册默认资源，再合并存档中的数值；保留存档的结算时间以便补发离线收益
+        let now = utils::now_ms() as u64;
+        let mut mgr = DQWMResourceMgr::default();
+        for r in resource::default_resources(now) {
+            mgr.register(r);
+        }
+        mgr.restore(state.resources);
+        mgr.recompute(&bonuses);
+        let resource_mgr = Arc::new(Mutex::new(mgr));
+        let (resource_tx, resource_rx) = flume::bounded::<ResourceOutput>(10);
+        // 旧存档没有保存时间，离线时长按 0 计
+        let last_seen = if state.saved_at > 0 {
+            state.saved_at
+        } else {
+            now
+        };
+        resource::spawn(resource_mgr.clone(), resource_tx, last_seen);
+        let (autosave_tx, autosave_rx) = flume::unbounded::<PlayerState>();
 
-        Self {
+        let mut app = Self {
             battle_rx: None,
+            battle_backlog: 0,
             current_army: Default::default(),
             current_event: Default::default(),
+            anims: Default::default(),
+            report: None,
+            resource_mgr,
+            resource_rx,
+            bonuses,
+            resources: Default::default(),
+            offline: None,
+            unit_upgrades: state.unit_upgrades,
+            progress: state.progress,
+            formation: state.formation,
+            autosave_tx,
+            focused: true,
+            last_frame: Instant::now(),
+            last_seen_ms: now,
+            battle: None,
+            auto_battle: false,
+            finished_at: None,
+            speed: BattleSpeed::default(),
+            resync_pending: false,
+            router: Router::new(Route::Home),
+            settings_rx,
+        };
+        app.on_settings(ctx, CONFIG.settings());
+        save::spawn_autosave(app.player_state(), app.resource_mgr.clone(), autosave_rx);
+        app
+    }
+
+    // 当前需要存档的玩家数据
+    pub fn player_state(&self) -> PlayerState {
+        PlayerState {
+            saved_at: utils::now_ms() as u64,
+            resources: resource::lock(&self.resource_mgr).states(),
+            unit_upgrades: self.unit_upgrades.clone(),
+            bonuses: self.bonuses.iter().cloned().collect(),
+            progress: self.progress.clone(),
+            formation: self.formation.clone(),
+        }
+    }
+}
+
+impl Application {
+    // 添加加成来源，立即重算资源产量；单位属性在下次生成军队时生效
+    pub fn add_bonus(&mut self, bonus: Bonus) {
+        self.bonuses.add(bonus);
+        resource::lock(&self.resource_mgr).recompute(&self.bonuses);
+        self.request_save();
+    }
+
+    pub fn remove_bonus(&mut self, id: u32) -> Option<Bonus> {
+        let removed = self.bonuses.remove(id);
+        resource::lock(&self.resource_mgr).recompute(&self.bonuses);
+        self.request_save();
+        removed
+    }
+
+    pub fn battle_status(&self) -> BattleStatus {
+        match (&self.battle, &self.report) {
+            (None, _) => BattleStatus::Idle,
+            (Some(_), None) => BattleStatus::Running,
+            (Some(_), Some(_)) => BattleStatus::Finished,
+        }
+    }
+
+    // 执行界面命令
+    pub fn execute(&mut self, command: Command) {
+        log::info!("执行命令 {:?}", command);
+        match command {
+            Command::StartBattle => self.start_battle(),
+            Command::ToggleAuto => {
+                self.auto_battle = !self.auto_battle;
+                if self.auto_battle && self.battle_status() == BattleStatus::Idle {
+                    self.start_battle();
+                }
+            }
+            // 速度也是设置项，经设置通知生效
+            Command::CycleSpeed => settings::update(Settings {
+                battle_speed: self.speed.next(),
+                ..CONFIG.settings()
+            }),
+            Command::UpdateSettings(settings) => settings::update(settings),
+            Command::Push(route) => {
+                self.router.push(route);
+                self.enter();
+            }
+            Command::Pop => {
+                if let Some(page) = self.router.pop() {
+                    self.leave(page);
+                }
+            }
+            Command::Replace(route) => {
+                let page = self.router.replace(route);
+                self.leave(page);
+                self.enter();
+            }
+            Command::SaveFormation => {
+                if let Page::Army(state) = self.router.current() {
+                    self.formation = state.formation();
+                    self.request_save();
+                }
+            }
+        }
+    }
+
+    // 处理战斗任务的输出
+    fn poll_battle(&mut self) {
+        self.battle_backlog = self.battle_rx.as_ref().map_or(0, Receiver::len);
+        if let Some(rx) = &self.battle_rx {
+            // 只有非空时才执行以下所有逻辑
+            while let Ok(out) = rx.try_recv() {
+                match out {
+                    BattleOutput::ArmySnapshot(army) => {
+                        self.current_army = army;
+                        self.resync_pending = false;
+                    }
+                    // 增量序号不连续时请求全量快照，收到之前的增量都丢弃
+                    BattleOutput::ArmyDiff(diff) => {
+                        if !self.resync_pending && !self.current_army.apply(&diff) {
+                            log::warn!(
+                                "阵型增量不连续：本地 {}，收到 {}，请求重新同步",
+                                self.current_army.seq,
+                                diff.seq
+                            );
+                            self.resync_pending = true;
+                            if let Some(battle) = &self.battle {
+                                battle.resync();
+                            }
+                        }
+                    }
+                    BattleOutput::BattleEvent(event) => {
+                        log::debug!("当前事件{:?}", event);
+                        self.current_event.push(Instant::now(), event);
+                    }
+                    BattleOutput::Finished(report) => {
+                        match report.winner {
+                            Some(Faction::Ally) => self.progress.battles_won += 1,
+                            Some(Faction::Enemy) => self.progress.battles_lost += 1,
+                            None => {}
+                        }
+                        self.report = Some(report);
+                        self.finished_at = Some(Instant::now());
+                        self.request_save();
+                    }
+                }
+            }
+        }
+    }
+
+    // 设置变化：主题、动画质量、战斗速度、语言立即生效，列数在下一场战斗和进入阵型页时生效
+    fn on_settings(&mut self, ctx: &Context, settings: Settings) {
+        ctx.set_visuals(settings.theme.visuals());
+        self.anims.set_quality(settings.anim_quality);
+        if settings.battle_speed != self.speed {
+            self.speed = settings.battle_speed;
+            if let Some(battle) = &self.battle {
+                battle.set_speed(self.speed);
+            }
+        }
+        if settings.language == Language::Chinese {
+            i18n::load_cjk_font(ctx);
+        }
+    }
+
+    // 新页面入栈后的初始化：阵型页载入当前阵型
+    fn enter(&mut self) {
+        if self.router.route() == Route::Army {
+            let army = self.ally_army();
+            if let Page::Army(state) = self.router.current_mut() {
+                state.load(army);
+            }
+        }
+    }
+
+    // 页面出栈后的清理：离开战斗页时中止战斗
+    fn leave(&mut self, page: Page) {
+        if let Page::Battle = page {
+            self.auto_battle = false;
+            self.abort_battle();
+        }
+    }
+
+    // 渲染当前页面
+    fn page_ui(&mut self, ui: &mut Ui) -> Option<Command> {
+        let status = self.battle_status();
+        match self.router.current_mut() {
+            Page::Home => home_page::render(ui, &self.progress),
+            Page::Battle => battle_page::render(
+                ui,
+                &self.current_army,
+                &self.anims,
+                self.report.as_ref(),
+                status,
+                self.auto_battle,
+                self.speed,
+            ),
+            Page::Army(state) => {
+                army_page::render(ui, state, CONFIG.max_ally_cols.load(Ordering::Relaxed))
+            }
+            Page::Settings => settings_page::render(ui, &CONFIG.settings()),
+            Page::Resource(state) => {
+                resource_page::render(ui, state, &self.resources, &self.bonuses)
+            }
+        }
+    }
+
+    // 我方军队：按保存的阵型生成，没有阵型时使用默认军队；均叠加当前加成
+    fn ally_army(&self) -> Vec<VecDeque<Unit>> {
+        let max_cols = CONFIG.max_ally_cols.load(Ordering::Relaxed);
+        if self.formation.is_empty() {
+            model::test_with_bonuses(120, max_cols, &self.bonuses)
+        } else {
+            self.formation
+                .build(&self.unit_upgrades, &self.bonuses, max_cols)
+        }
+    }
+
+    // 本场战斗的军队：敌方为测试军队
+    fn next_army(&self) -> Army {
+        Army {
+            enemys: model::test(120, CONFIG.max_enemy_cols.load(Ordering::Relaxed)),
+            allys: self.ally_army(),
+        }
+    }
+
+    // 用当前军队开始战斗，已有的战斗先结束
+    fn start_battle(&mut self) {
+        self.abort_battle();
+        let (battle_tx, battle_rx) = flume::bounded::<BattleOutput>(delivery::CHANNEL_CAPACITY);
+        let seed = utils::now_ms() as u64;
+        log::info!("开始战斗，种子：{}", seed);
+        let ctx = BattleContext {
+            speed: self.speed,
+            ..BattleContext::new(self.next_army(), seed, battle_tx)
+        };
+        self.battle_rx = Some(battle_rx);
+        self.battle = Some(BattleExecutor::spawn(ctx));
+    }
+
+    // 结束战斗任务并清空战场
+    fn abort_battle(&mut self) {
+        if let Some(battle) = self.battle.take() {
+            battle.cancel(); // 句柄丢弃时任务中止
+        }
+        self.battle_rx = None;
+        self.resync_pending = false;
+        self.current_army = Default::default();
+        self.current_event.clear();
+        self.anims.clear();
+        self.report = None;
+        self.finished_at = None;
+    }
+
+    // 把最新玩家数据交给自动存档任务写入，不阻塞界面
+    fn request_save(&self) {
+        if self.autosave_tx.send(self.player_state()).is_err() {
+            log::error!("自动存档任务已退出");
+        }
+    }
+
+    // 前后台切换：失去焦点立即存档（安卓切到后台后进程随时可能被杀）；
+    // 重新获得焦点或长时间没有绘制（被挂起）后补发离线收益，离线时长从上次在前台算起
+    fn lifecycle(&mut self, ctx: &Context) {
+        const RESUME_GAP: Duration = Duration::from_secs(2);
+        let focused = ctx.input(|i| i.focused);
+        let resumed = (focused && !self.focused) || self.last_frame.elapsed() > RESUME_GAP;
+        if !focused && self.focused {
+            log::info!("失去焦点，存档");
+            self.request_save();
+        }
+        if resumed {
+            let mut mgr = resource::lock(&self.resource_mgr);
+            let earnings = mgr.catch_up(utils::now_ms() as u64, self.last_seen_ms);
+            self.resources = mgr.iter().map(|r| (r.id, r.clone())).collect();
+            drop(mgr);
+            if !earnings.gains.is_empty() {
+                log::info!(
+                    "恢复前台，离线 {} 毫秒，补发收益 {:?}",
+                    earnings.elapsed_ms,
+                    earnings.gains
+                );
+                self.offline = Some((Instant::now(), earnings));
+            }
+        }
+        self.focused = focused;
+        self.last_frame = Instant::now();
+        if focused {
+            self.last_seen_ms = utils::now_ms() as u64;
+        }
+    }
+
+    // 右上角资源栏，启动时附带显示离线收益；悬停显示产量构成
+    fn resource_ui(&mut self, ui: &Ui) {
+        const OFFLINE_SHOW: Duration = Duration::from_secs(5);
+        let font = FontId::proportional(ui.rem(0.22));
+        let gap = ui.rem(0.2);
+        let top = ui.max_rect().top() + ui.rem(0.1);
+        let mut right = ui.max_rect().right() - ui.rem(0.1);
+        let mut bottom = top;
+
+        let p = ui.painter();
+        for r in self.resources.values().rev() {
+            let rect = p.text(
+                pos2(right, top),
+                Align2::RIGHT_TOP,
+                format!("{} {}", r.name, r.value),
+                font.clone(),
+                ui.visuals().strong_text_color(),
+            );
+            right = rect.min.x - gap;
+            bottom = bottom.max(rect.max.y);
+
+            let breakdown = self
+                .bonuses
+                .breakdown(BonusTarget::ResourceChange(r.id), r.base_change as f64);
+            ui.interact(rect, Id::new(("resource", r.id)), Sense::hover())
+                .on_hover_text(format!(
+                    "{}\n{}/{}s\n{}",
+                    r.description,
+                    r.change,
+                    r.interval as f32 / 1000.0,
+                    breakdown
+                ));
+        }
+
+        if let Some((at, earnings)) = &self.offline {
+            if at.elapsed() > OFFLINE_SHOW {
+                self.offline = None;
+                return;
+            }
+            let gains = earnings
+                .gains
+                .iter()
+                .filter_map(|(id, v)| self.resources.get(id).map(|r| format!("+{} {}", v, r.name)))
+                .collect::<Vec<_>>()
+                .join("  ");
+            p.text(
+                pos2(ui.max_rect().right() - ui.rem(0.1), bottom),
+                Align2::RIGHT_TOP,
+                format!(
+                    "{} {}s  {}",
+                    i18n::tr("Offline"),
+                    earnings.elapsed_ms / 1000,
+                    gains
+                ),
+                font,
+                Color32::from_rgb(40, 160, 60),
+            );
         }
     }
 }
@@ -157,77 +555,87 @@ impl App for Application {
         style.interaction.selectable_labels = false; // ← 关掉，否则文本会有选中态
         style.spacing.item_spacing = Vec2::ZERO;
         ctx.set_style(style);
+        self.lifecycle(ctx);
+        while let Ok(settings) = self.settings_rx.try_recv() {
+            self.on_settings(ctx, settings);
+        }
 
         CentralPanel::default().frame(Frame::NONE).show(ctx, |ui| {
-            ui.painter()
-                .rect_filled(ctx.viewport_rect(), 0.0, Color32::WHITE);
-
-            // battle_page::render(ui, &self.utils);
-
-            if let Some(ref mut rx) = self.battle_rx {
-                // 只有非空时才执行以下所有逻辑
-                while let Ok(out) = rx.try_recv() {
-                    match out {
-                        BattleOutput::ArmySnapshot(army) => {
-                            self.current_army = army;
-                        }
-                        BattleOutput::BattleEvent(event) => {
-                            println!("当前事件{:?}", event);
-                            self.current_event.push_back(event);
+            ui.painter().rect_filled(
+                ctx.viewport_rect(),
+                0.0,
+                ctx.style().visuals.extreme_bg_color,
+            );
 
-                            // 自动限制最多 50 条：超出就从前面弹出
-                            if self.current_event.len() > 50 {
-                                self.current_event.pop_front();
-                            }
-                        }
+            while let Ok(out) = self.resource_rx.try_recv() {
+                match out {
+                    ResourceOutput::Snapshot(list) => {
+                        self.resources = list.into_iter().map(|r| (r.id, r)).collect();
+                    }
+                    ResourceOutput::Updated(list) => {
+                        self.resources.extend(list.into_iter().map(|r| (r.id, r)));
+                    }
+                    ResourceOutput::Offline(earnings) => {
+                        self.offline = Some((Instant::now(), earnings));
                     }
                 }
             }
-            let (a, b, c) = battle_page::render(ui, &self.current_army, &self.current_event);
-
-            if a.clicked() {
-                let (battle_tx, battle_rx) = flume::bounded::<BattleOutput>(10);
-                self.battle_rx = Some(battle_rx);
-                global_tokio_runtime().spawn(async move {
-                    log::info!("开始==》");
-                    let _ = battle_tx.send(BattleOutput::ArmySnapshot(ArmySnapshot {
-                        enemys: model::test(120),
-                        allys: model::test(120),
-                        enemys_num: 120,
-                        allys_num: 120,
-                    }));
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                    loop {
-                        for i in [1, 2, 3, 4, 5, 6] {
-                            let _ = battle_tx.send(BattleOutput::BattleEvent(BattleEvent::atk(i)));
-                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                        }
-                        for i in [121, 122, 123, 124, 125, 126] {
-                            let _ = battle_tx
-                                .send(BattleOutput::BattleEvent(BattleEvent::def(i, i * 10)));
-                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                        }
-                    }
-                });
+
+            self.poll_battle();
+
+            // 新事件只消费一次，生成的动画各自独立播放
+            self.anims
+                .feed(&self.current_event, self.speed.multiplier().unwrap_or(1.0));
+            self.anims.prune(Instant::now());
+
+            let stats = DebugStats {
+                events: self.current_event.len(),
+                anims: self.anims.len(),
+                battle_backlog: self.battle_backlog,
+            };
+            ctx.data_mut(|d| d.insert_temp(Id::new(Key::Debug), stats));
+
+            // 安卓返回键（桌面为 Esc）返回上一页
+            let back = ctx.input_mut(|i| {
+                i.consume_key(Modifiers::NONE, egui::Key::BrowserBack)
+                    || i.consume_key(Modifiers::NONE, egui::Key::Escape)
+            });
+            if back {
+                self.execute(Command::Pop);
             }
-            if b.clicked() {
-                global_tokio_runtime().spawn(async {
-                    log::info!("开始==》");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(6)).await;
-                    // 后台逻辑写在这里（不能操作 UI）
-                });
+
+            // 页面切换时当前页面水平滑入
+            let offset = self.router.offset(ui.max_rect().width());
+            let page_rect = ui.max_rect().translate(vec2(offset, 0.0));
+            let command = ui
+                .scope_builder(UiBuilder::new().max_rect(page_rect), |ui| self.page_ui(ui))
+                .inner;
+
+            self.resource_ui(ui);
+
+            if let Some(command) = command {
+                self.execute(command);
             }
-            if c.clicked() {
-                global_tokio_runtime().spawn(async {
-                    log::info!("开始==》");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(9)).await;
-                    // 后台逻辑写在这里（不能操作 UI）
-                });
+            // 自动战斗：结算显示一段时间后开始下一场
+            if self.auto_battle
+                && self
+                    .finished_at
+                    .is_some_and(|at| at.elapsed() >= AUTO_BATTLE_DELAY)
+            {
+                self.execute(Command::StartBattle);
             }
         });
 
         ctx.request_repaint(); // 立即刷新
     }
+
+    // eframe 定时（auto_save_interval）、安卓挂起及退出时调用；同步写入，保证进程被杀前落盘
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        match save::save(&self.player_state()) {
+            Ok(()) => log::info!("已存档"),
+            Err(e) => log::error!("存档失败: {}", e),
+        }
+    }
 }
 
 pub fn run(options: NativeOptions) {
@@ -249,7 +657,13 @@ extern "Rust" fn android_main(app: winit::platform::android::activity::AndroidAp
         android_logger::Config::default().with_max_level(log::LevelFilter::Info),
     );
 
+    // 安卓没有默认存储目录，指定应用内部目录下的文件后 eframe 才会调用 App::save
+    let data_dir = app.internal_data_path();
+    if let Some(dir) = &data_dir {
+        save::set_save_dir(dir.clone());
+    }
     let options = eframe::NativeOptions {
+        persistence_path: data_dir.map(|d| d.join("app.ron")),
         android_app: Some(app),
         ..Default::default()
     };
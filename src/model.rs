@@ -1,37 +1,302 @@
 use std::{
-    collections::VecDeque,
-    sync::atomic::{AtomicUsize, Ordering},
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
+use serde::{Deserialize, Serialize};
+
+use crate::{core::bonus::BonusManager, data};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Faction {
     Ally,
     Enemy,
 }
+impl Faction {
+    // 对手阵营
+    pub fn opponent(self) -> Self {
+        match self {
+            Faction::Ally => Faction::Enemy,
+            Faction::Enemy => Faction::Ally,
+        }
+    }
+}
+
+/// 1. 策划配表，全局只读
+#[derive(Debug, Clone, Deserialize)]
+pub struct BaseUnitTemp {
+    pub id: u32,                // id
+    pub name: Arc<str>,         // 名字
+    pub base_atk: u128,         // 攻击
+    pub base_hp: u128,          // 生命
+    pub base_def: u128,         // 防御
+    pub base_speek: u64,        // 攻击间隔（毫秒）
+    pub base_crit_rate: f64,    // 暴击率
+    pub base_crit_damage: f64,  // 暴击伤害
+    pub base_hit_rate: f64,     // 命中率
+    pub base_dodge_rate: f64,   // 闪避率
+    pub base_attack_speed: f64, // 攻击速度
+    pub base_life_steal: f64,   // 生命偷取
+}
+
+/// 基础模板注册表：BaseUnitTemp.id -> 模板
+#[derive(Debug, Clone, Default)]
+pub struct BaseUnitRegistry {
+    temps: BTreeMap<u32, BaseUnitTemp>,
+}
+impl BaseUnitRegistry {
+    pub fn new(temps: impl IntoIterator<Item = BaseUnitTemp>) -> Self {
+        Self {
+            temps: temps.into_iter().map(|t| (t.id, t)).collect(),
+        }
+    }
+
+    pub fn get(&self, id: u32) -> Option<&BaseUnitTemp> {
+        self.temps.get(&id)
+    }
+
+    // 按 id 升序遍历
+    pub fn iter(&self) -> impl Iterator<Item = &BaseUnitTemp> {
+        self.temps.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.temps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.temps.is_empty()
+    }
+}
+
+// 全局单位配表，首次使用时从配表文件加载
+pub static BASE_UNITS: LazyLock<BaseUnitRegistry> = LazyLock::new(data::load_base_units_or_bundled);
+
+/// 账号养成数据：等级 + 各项固定加成
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnitUpgrades {
+    pub level: u32, // 等级，0 与 1 等价
+    pub atk: u128,
+    pub hp: u128,
+    pub def: u128,
+    pub crit_rate: f64,
+    pub crit_damage: f64,
+    pub hit_rate: f64,
+    pub dodge_rate: f64,
+    pub attack_speed: f64,
+    pub life_steal: f64,
+}
+
+/// 2. 账号维度：在基础值上叠加养成、装备、科技等加成，只存“当前值”，不存历史 delta
+#[derive(Debug, Clone)]
+pub struct UnitTemp {
+    pub base_temp_id: u32, // 对应 BaseUnitTemp.id
+    pub name: Arc<str>,
+    pub level: u32,
+    pub atk: u128, // 当前攻击
+    pub hp: u128,
+    pub def: u128,
+    pub speek: u64,        // 攻击间隔（毫秒）
+    pub crit_rate: f64,    // 暴击率
+    pub crit_damage: f64,  // 暴击伤害
+    pub hit_rate: f64,     // 命中率
+    pub dodge_rate: f64,   // 闪避率
+    pub attack_speed: f64, // 攻击速度
+    pub life_steal: f64,   // 生命偷取
+}
+impl UnitTemp {
+    // 每级攻击、生命、防御提升基础值的 10%
+    const LEVEL_GROWTH: u128 = 10;
+
+    // 基础模板 + 养成 => 账号模板
+    pub fn derive(base: &BaseUnitTemp, upgrades: &UnitUpgrades) -> Self {
+        let growth =
+            |v: u128| v + v * Self::LEVEL_GROWTH * upgrades.level.saturating_sub(1) as u128 / 100;
+        Self {
+            base_temp_id: base.id,
+            name: base.name.clone(),
+            level: upgrades.level.max(1),
+            atk: growth(base.base_atk) + upgrades.atk,
+            hp: growth(base.base_hp) + upgrades.hp,
+            def: growth(base.base_def) + upgrades.def,
+            speek: base.base_speek,
+            crit_rate: (base.base_crit_rate + upgrades.crit_rate).clamp(0.0, 1.0),
+            crit_damage: base.base_crit_damage + upgrades.crit_damage,
+            hit_rate: (base.base_hit_rate + upgrades.hit_rate).clamp(0.0, 1.0),
+            dodge_rate: (base.base_dodge_rate + upgrades.dodge_rate).clamp(0.0, 1.0),
+            attack_speed: base.base_attack_speed + upgrades.attack_speed,
+            life_steal: (base.base_life_steal + upgrades.life_steal).max(0.0),
+        }
+    }
+
+    /// 3. 生成战斗单位，每次分配新的 id
+    pub fn instantiate(&self) -> Unit {
+        Unit {
+            id: NEXT_UNIT_ID.fetch_add(1, Ordering::Relaxed),
+            temp_id: self.base_temp_id,
+            name: self.name.clone(),
+            level: self.level,
+            hp: self.hp,
+            max_hp: self.hp,
+            atk: self.atk,
+            def: self.def,
+            speek: self.speek,
+            crit_rate: self.crit_rate,
+            crit_damage: self.crit_damage,
+            hit_rate: self.hit_rate,
+            dodge_rate: self.dodge_rate,
+            attack_speed: self.attack_speed,
+            life_steal: self.life_steal,
+        }
+    }
+}
 
-#[derive(Clone, Debug)]
+// 战斗单位 id：单位、战斗事件、动画 Id 统一使用
+pub type UnitId = usize;
 
+/// 3. 战斗单位：战斗中的临时实例
+#[derive(Clone, Debug, PartialEq)]
 pub struct Unit {
-    pub id: usize,
-    pub name: &'static str,
+    pub id: UnitId,
+    pub temp_id: u32, // 对应 BaseUnitTemp.id
+    pub name: Arc<str>,
+    pub level: u32,
     pub hp: u128,
     pub max_hp: u128,
     pub atk: u128,
     pub def: u128,
-    pub speek: u64, // 攻击间隔（毫秒）
+    pub speek: u64,        // 攻击间隔（毫秒）
+    pub crit_rate: f64,    // 暴击率
+    pub crit_damage: f64,  // 暴击伤害（倍率）
+    pub hit_rate: f64,     // 命中率
+    pub dodge_rate: f64,   // 闪避率
+    pub attack_speed: f64, // 攻击速度（倍率，实际间隔 = speek / attack_speed）
+    pub life_steal: f64,   // 生命偷取（按实际伤害比例回血）
 }
 impl Unit {
+    // 伤害 = 攻击 - 防御，保底 1 点，避免双方破不了防导致战斗无法结束
     pub fn calculate_damage_to(&self, target: &Unit) -> u128 {
-        self.atk.saturating_sub(target.def)
+        self.atk.saturating_sub(target.def).max(1)
+    }
+
+    // 暴击伤害
+    pub fn calculate_crit_damage_to(&self, target: &Unit) -> u128 {
+        (self.calculate_damage_to(target) as f64 * self.crit_damage.max(1.0)) as u128
+    }
+
+    // 实际攻击间隔（毫秒），至少 1
+    pub fn attack_interval(&self) -> u64 {
+        if self.attack_speed <= 0.0 {
+            return self.speek.max(1);
+        }
+        ((self.speek as f64 / self.attack_speed) as u64).max(1)
     }
 
     pub fn is_alive(&self) -> bool {
         self.hp > 0
     }
+
+    // 回血，不超过上限，返回实际回复量
+    pub fn heal(&mut self, amount: u128) -> u128 {
+        let healed = amount.min(self.max_hp.saturating_sub(self.hp));
+        self.hp += healed;
+        healed
+    }
+}
+
+/// 阵型中的一个单位：只记模板，等级等养成取账号数据，开战时再生成战斗单位
+/// 旧存档里的 level 字段忽略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormationSlot {
+    pub temp_id: u32, // 对应 BaseUnitTemp.id
+}
+
+/// 玩家阵型：列 -> 行，行 0 为最前排
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Formation {
+    pub cols: Vec<Vec<FormationSlot>>,
+}
+impl Formation {
+    // 从战斗单位记录阵型，空列丢弃
+    pub fn from_army(army: &[VecDeque<Unit>]) -> Self {
+        let cols = army
+            .iter()
+            .filter(|col| !col.is_empty())
+            .map(|col| {
+                col.iter()
+                    .map(|u| FormationSlot { temp_id: u.temp_id })
+                    .collect()
+            })
+            .collect();
+        Self { cols }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cols.iter().all(Vec::is_empty)
+    }
+
+    // 生成战斗单位：叠加账号养成和加成，配表中已不存在的模板跳过
+    // 列数超过 max_cols 时多出的单位补到前面各列
+    pub fn build(
+        &self,
+        upgrades: &BTreeMap<u32, UnitUpgrades>,
+        bonuses: &BonusManager,
+        max_cols: usize,
+    ) -> Vec<VecDeque<Unit>> {
+        let mut cols = self
+            .cols
+            .iter()
+            .map(|col| {
+                col.iter()
+                    .filter_map(|slot| {
+                        let base = BASE_UNITS.get(slot.temp_id)?;
+                        let upgrades = upgrades.get(&slot.temp_id).cloned().unwrap_or_default();
+                        Some(
+                            bonuses
+                                .apply_unit(&UnitTemp::derive(base, &upgrades))
+                                .instantiate(),
+                        )
+                    })
+                    .collect::<VecDeque<_>>()
+            })
+            .filter(|col| !col.is_empty())
+            .collect();
+        fit_columns(&mut cols, max_cols);
+        cols
+    }
+}
+
+// 列数超过上限时，多出的列按行主序依次补到前面各列末尾
+pub fn fit_columns(cols: &mut Vec<VecDeque<Unit>>, max_cols: usize) {
+    let max_cols = max_cols.max(1);
+    if cols.len() <= max_cols {
+        return;
+    }
+    let mut extra = cols.split_off(max_cols);
+    let rows = extra.iter().map(VecDeque::len).max().unwrap_or(0);
+    let mut i = 0;
+    for _ in 0..rows {
+        for unit in extra.iter_mut().filter_map(VecDeque::pop_front) {
+            cols[i % max_cols].push_back(unit);
+            i += 1;
+        }
+    }
 }
 
 static NEXT_UNIT_ID: AtomicUsize = AtomicUsize::new(1);
-pub fn test(num: usize) -> Vec<VecDeque<Unit>> {
+pub fn test(num: usize, max_cols: usize) -> Vec<VecDeque<Unit>> {
+    test_with_bonuses(num, max_cols, &BonusManager::default())
+}
+
+// 生成测试军队，单位属性叠加加成，列数不超过 max_cols
+pub fn test_with_bonuses(
+    num: usize,
+    max_cols: usize,
+    bonuses: &BonusManager,
+) -> Vec<VecDeque<Unit>> {
     if num == 0 {
         return vec![];
     }
@@ -42,22 +307,23 @@ pub fn test(num: usize) -> Vec<VecDeque<Unit>> {
         10..=29 => 3,
         30..=59 => 4,
         60..=119 => 5,
-        _ => 6,
-    };
-    if n_cols == 0 {
-        return vec![];
+        n => 6 + (n - 120) / 60, // 之后每 60 个多一列
     }
+    .min(max_cols.max(1));
 
-    // 预生成所有单位（不指定 id，用 new 自动分配）
-    let units: Vec<Unit> = (1..=num)
-        .map(|i| Unit {
-            id: NEXT_UNIT_ID.fetch_add(1, Ordering::Relaxed),
-            name: "Unit",
-            hp: i as u128,
-            max_hp: i as u128,
-            atk: 10 + (i % 10) as u128,
-            def: 1,
-            speek: 100 + ((i % 100) * 2) as u64,
+    // 按内置配表轮流生成，等级 1~10
+    let temps: Vec<&BaseUnitTemp> = BASE_UNITS.iter().collect();
+    if temps.is_empty() {
+        return vec![];
+    }
+    let units: Vec<Unit> = (0..num)
+        .map(|i| {
+            let upgrades = UnitUpgrades {
+                level: 1 + (i % 10) as u32,
+                ..Default::default()
+            };
+            let temp = UnitTemp::derive(temps[i % temps.len()], &upgrades);
+            bonuses.apply_unit(&temp).instantiate()
         })
         .collect();
 
@@ -71,3 +337,37 @@ pub fn test(num: usize) -> Vec<VecDeque<Unit>> {
 
     columns
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 阵型不保存等级：默认军队的等级不会变成免费养成，旧存档的 level 忽略
+    #[test]
+    fn formation_level_comes_from_account() {
+        let army = test(20, 2);
+        assert!(army.iter().flatten().any(|u| u.level > 1));
+        let formation = Formation::from_army(&army);
+        let bonuses = BonusManager::default();
+
+        let built = formation.build(&BTreeMap::new(), &bonuses, 2);
+        assert!(built.iter().flatten().all(|u| u.level == 1));
+
+        let temp_id = army[0][0].temp_id;
+        let upgrades = BTreeMap::from([(
+            temp_id,
+            UnitUpgrades {
+                level: 7,
+                ..Default::default()
+            },
+        )]);
+        let built = formation.build(&upgrades, &bonuses, 2);
+        for u in built.iter().flatten() {
+            assert_eq!(u.level, if u.temp_id == temp_id { 7 } else { 1 });
+        }
+
+        let old: Formation = serde_json::from_str(r#"{"cols": [[{"temp_id": 1, "level": 9}]]}"#)
+            .expect("读取旧阵型失败");
+        assert_eq!(old.cols, [[FormationSlot { temp_id: 1 }]]);
+    }
+}
@@ -0,0 +1,141 @@
+// 页面栈：push 进入新页面，pop 返回上一页，replace 替换当前页
+// 页面状态随页面入栈创建、出栈销毁；栈底页面不会被弹出
+
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+use crate::components::{army_page, resource_page};
+
+const TRANSITION: Duration = Duration::from_millis(250); // 切换动画时长
+
+// 页面路由，页面通过 Command 请求跳转
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    Home,     // 首页
+    Battle,   // 战斗
+    Army,     // 阵型
+    Resource, // 资源
+    Settings, // 设置
+}
+
+// 栈中的页面及其状态
+#[derive(Debug)]
+pub enum Page {
+    Home,
+    Battle,
+    Army(army_page::State),
+    Resource(resource_page::State),
+    Settings,
+}
+impl Page {
+    fn new(route: Route) -> Self {
+        match route {
+            Route::Home => Page::Home,
+            Route::Battle => Page::Battle,
+            Route::Army => Page::Army(Default::default()),
+            Route::Resource => Page::Resource(Default::default()),
+            Route::Settings => Page::Settings,
+        }
+    }
+
+    pub fn route(&self) -> Route {
+        match self {
+            Page::Home => Route::Home,
+            Page::Battle => Route::Battle,
+            Page::Army(_) => Route::Army,
+            Page::Resource(_) => Route::Resource,
+            Page::Settings => Route::Settings,
+        }
+    }
+}
+
+// 切换方向：前进从右侧滑入，后退从左侧滑入
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    start: Instant,
+    dir: Direction,
+}
+
+#[derive(Debug)]
+pub struct Router {
+    current: Page,
+    below: Vec<Page>, // 当前页面之下的页面，越靠后越新
+    transition: Option<Transition>,
+}
+impl Router {
+    pub fn new(root: Route) -> Self {
+        Self {
+            current: Page::new(root),
+            below: vec![],
+            transition: None,
+        }
+    }
+
+    pub fn current(&self) -> &Page {
+        &self.current
+    }
+
+    pub fn current_mut(&mut self) -> &mut Page {
+        &mut self.current
+    }
+
+    pub fn route(&self) -> Route {
+        self.current.route()
+    }
+
+    // 栈深度，至少为 1
+    pub fn depth(&self) -> usize {
+        self.below.len() + 1
+    }
+
+    pub fn push(&mut self, route: Route) {
+        let prev = mem::replace(&mut self.current, Page::new(route));
+        self.below.push(prev);
+        self.animate(Direction::Forward);
+    }
+
+    // 返回被弹出的页面；已在栈底时不弹出
+    pub fn pop(&mut self) -> Option<Page> {
+        let prev = self.below.pop()?;
+        self.animate(Direction::Backward);
+        Some(mem::replace(&mut self.current, prev))
+    }
+
+    // 返回被替换的页面
+    pub fn replace(&mut self, route: Route) -> Page {
+        self.animate(Direction::Forward);
+        mem::replace(&mut self.current, Page::new(route))
+    }
+
+    fn animate(&mut self, dir: Direction) {
+        self.transition = Some(Transition {
+            start: Instant::now(),
+            dir,
+        });
+    }
+
+    // 当前页面的水平偏移，切换结束后为 0
+    pub fn offset(&mut self, width: f32) -> f32 {
+        let Some(transition) = self.transition else {
+            return 0.0;
+        };
+        let t = transition.start.elapsed().as_secs_f32() / TRANSITION.as_secs_f32();
+        if t >= 1.0 {
+            self.transition = None;
+            return 0.0;
+        }
+        let remain = (1.0 - t).powi(3); // ease-out
+        match transition.dir {
+            Direction::Forward => width * remain,
+            Direction::Backward => -width * remain,
+        }
+    }
+}
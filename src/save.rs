@@ -0,0 +1,389 @@
+// 存档：玩家数据序列化为带版本号的 JSON 文件
+// 先写临时文件再改名，旧存档保留为 .bak；读取旧版本存档时逐级迁移到当前版本
+// 安卓进程在后台随时可能被杀，除了 App::save 外还有后台定时自动存档
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use crate::{
+    APP_NAME,
+    core::{
+        bonus::Bonus,
+        resource::{self, DQWMResourceMgr, ResourceState},
+    },
+    global::global_tokio_runtime,
+    model::{Formation, UnitUpgrades},
+    utils,
+};
+
+// 当前存档版本
+pub const SAVE_VERSION: u32 = 1;
+// 存档文件名
+const SAVE_FILE: &str = "save.json";
+// 自动存档间隔
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// 界面线程（App::save）和自动存档任务可能同时写，写文件时串行
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// 本次运行中版本比程序新的存档：不再写入，否则连续写两次后原文件经 .bak 轮换被删掉
+// 升级程序后还能继续读取
+static UNREADABLE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// 存档目录（安卓在启动时设置为应用内部目录）
+static SAVE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// 设置存档目录，只有第一次调用生效
+pub fn set_save_dir(dir: PathBuf) {
+    let _ = SAVE_DIR.set(dir);
+}
+
+// 存档目录：优先使用 set_save_dir，否则桌面端与 eframe 存储目录相同
+pub fn save_dir() -> Option<PathBuf> {
+    SAVE_DIR
+        .get()
+        .cloned()
+        .or_else(|| eframe::storage_dir(APP_NAME))
+}
+
+pub fn save_path() -> Option<PathBuf> {
+    save_dir().map(|d| d.join(SAVE_FILE))
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    NoSaveDir,                   // 当前平台没有可用的存档目录
+    Io(PathBuf, std::io::Error), // 读写失败
+    Json(serde_json::Error),     // 格式错误
+    Version(u32),                // 存档版本比程序新，无法读取
+    Migrate(u32, String),        // 迁移失败：起始版本、原因
+    Unreadable(PathBuf),         // 本次运行版本比程序新的存档，不再写入
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::NoSaveDir => write!(f, "没有可用的存档目录"),
+            SaveError::Io(path, e) => write!(f, "读写存档 {} 失败: {}", path.display(), e),
+            SaveError::Json(e) => write!(f, "存档格式错误: {}", e),
+            SaveError::Version(v) => {
+                write!(f, "存档版本 {} 高于当前支持的版本 {}", v, SAVE_VERSION)
+            }
+            SaveError::Migrate(v, e) => write!(f, "存档从版本 {} 迁移失败: {}", v, e),
+            SaveError::Unreadable(path) => {
+                write!(f, "存档 {} 版本比程序新，本次运行不再写入", path.display())
+            }
+        }
+    }
+}
+impl std::error::Error for SaveError {}
+
+// 游戏进度
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    pub battles_won: u32,
+    pub battles_lost: u32,
+}
+
+// 玩家数据，缺少的字段取默认值，新增字段不必写迁移
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerState {
+    pub saved_at: u64,                 // 保存时间（unix 毫秒），启动时据此计算离线时长
+    pub resources: Vec<ResourceState>, // 资源：数值和结算时间
+    pub unit_upgrades: BTreeMap<u32, UnitUpgrades>, // 单位养成：BaseUnitTemp.id -> 养成
+    pub bonuses: Vec<Bonus>,           // 已获得的加成来源
+    pub progress: Progress,            // 进度
+    pub formation: Formation,          // 我方阵型，为空时使用默认军队
+}
+
+// 存档文件
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    state: PlayerState,
+}
+
+// 迁移函数：MIGRATIONS[v] 把版本 v 的存档转换为版本 v + 1
+type Migration = fn(Value) -> Result<Value, String>;
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] = [migrate_v0_to_v1];
+
+// 版本 0：没有版本号外壳，直接是玩家数据
+fn migrate_v0_to_v1(value: Value) -> Result<Value, String> {
+    if !value.is_object() {
+        return Err("存档根节点不是对象".to_string());
+    }
+    Ok(serde_json::json!({ "version": 1, "state": value }))
+}
+
+// 读取存档版本，没有 version 字段视为版本 0
+fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+// 把任意旧版本存档迁移到当前版本
+pub fn migrate(mut value: Value) -> Result<Value, SaveError> {
+    let mut version = version_of(&value);
+    if version > SAVE_VERSION {
+        return Err(SaveError::Version(version));
+    }
+    while version < SAVE_VERSION {
+        let step = MIGRATIONS[version as usize];
+        value = step(value).map_err(|e| SaveError::Migrate(version, e))?;
+        let next = version_of(&value);
+        if next <= version {
+            return Err(SaveError::Migrate(
+                version,
+                "迁移后版本号未增加".to_string(),
+            ));
+        }
+        version = next;
+    }
+    Ok(value)
+}
+
+pub fn from_json(text: &str) -> Result<PlayerState, SaveError> {
+    let value: Value = serde_json::from_str(text).map_err(SaveError::Json)?;
+    let file: SaveFile = serde_json::from_value(migrate(value)?).map_err(SaveError::Json)?;
+    Ok(file.state)
+}
+
+pub fn to_json(state: &PlayerState) -> Result<String, SaveError> {
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        state: state.clone(),
+    };
+    serde_json::to_string_pretty(&file).map_err(SaveError::Json)
+}
+
+// 备份文件路径：save.json -> save.json.bak
+fn backup_path(path: &Path) -> PathBuf {
+    let mut p = path.as_os_str().to_owned();
+    p.push(".bak");
+    PathBuf::from(p)
+}
+
+// 写存档；本次运行版本比程序新的存档不写
+pub fn write_to(path: &Path, state: &PlayerState) -> Result<(), SaveError> {
+    let unreadable = UNREADABLE.lock().unwrap_or_else(PoisonError::into_inner);
+    if unreadable.iter().any(|p| p == path) {
+        return Err(SaveError::Unreadable(path.to_path_buf()));
+    }
+    drop(unreadable);
+    write_atomic(path, &to_json(state)?)
+}
+
+// 原子写文件：先写 .tmp 并刷盘，旧文件改名为 .bak，再把 .tmp 改名为正式文件
+pub(crate) fn write_atomic(path: &Path, text: &str) -> Result<(), SaveError> {
+    let io = |e| SaveError::Io(path.to_path_buf(), e);
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    {
+        let mut file = fs::File::create(&tmp).map_err(io)?;
+        file.write_all(text.as_bytes()).map_err(io)?;
+        file.sync_all().map_err(io)?;
+    }
+    if path.exists() {
+        fs::rename(path, backup_path(path)).map_err(io)?;
+    }
+    fs::rename(&tmp, path).map_err(io)
+}
+
+// 损坏的存档改名保留为 .corrupt-<unix 毫秒>，之后的写入和 .bak 轮换都不会碰到
+fn set_aside(path: &Path) {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(format!(".corrupt-{}", utils::now_ms()));
+    let aside = PathBuf::from(aside);
+    match fs::rename(path, &aside) {
+        Ok(()) => log::warn!("损坏的存档已改名为 {}", aside.display()),
+        Err(e) => log::error!("损坏的存档 {} 改名失败: {}", path.display(), e),
+    }
+}
+
+// 读存档：正式存档损坏时尝试 .bak；都不存在返回 None
+// 版本比程序新：不读 .bak（备份是更旧的数据），本次运行不再写入该存档
+// 其他错误：把读不了的文件改名保留，之后照常存档
+pub fn read_from(path: &Path) -> Result<Option<PlayerState>, SaveError> {
+    let read = |p: &Path| -> Result<Option<PlayerState>, SaveError> {
+        let result = match fs::read_to_string(p) {
+            Ok(text) => from_json(&text).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SaveError::Io(p.to_path_buf(), e)),
+        };
+        match &result {
+            Err(SaveError::Version(_)) => UNREADABLE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(path.to_path_buf()),
+            Err(_) => set_aside(p),
+            Ok(_) => {}
+        }
+        result
+    };
+    match read(path) {
+        Ok(Some(state)) => Ok(Some(state)),
+        Ok(None) => read(&backup_path(path)),
+        Err(e @ SaveError::Version(_)) => Err(e),
+        Err(e) => {
+            log::error!("{}，尝试读取备份存档", e);
+            read(&backup_path(path)).and_then(|s| s.map(Some).ok_or(e))
+        }
+    }
+}
+
+// 保存到默认存档目录
+pub fn save(state: &PlayerState) -> Result<(), SaveError> {
+    let path = save_path().ok_or(SaveError::NoSaveDir)?;
+    write_to(&path, state)
+}
+
+// 从默认存档目录读取
+pub fn load() -> Result<Option<PlayerState>, SaveError> {
+    let path = save_path().ok_or(SaveError::NoSaveDir)?;
+    read_from(&path)
+}
+
+// 自动存档：界面每次玩家数据变化（战斗结束、加成变化、失去焦点等）把最新数据发过来，立即写入；
+// 另外每隔 AUTOSAVE_INTERVAL 用资源管理里的最新资源刷新一次，保证挂机收益不会丢；界面关闭通道后退出
+pub fn spawn_autosave(
+    initial: PlayerState,
+    mgr: Arc<Mutex<DQWMResourceMgr>>,
+    rx: flume::Receiver<PlayerState>,
+) -> JoinHandle<()> {
+    global_tokio_runtime().spawn(async move {
+        let mut latest = initial;
+        let mut timer = tokio::time::interval(AUTOSAVE_INTERVAL);
+        timer.tick().await; // 第一次立即触发，跳过
+        loop {
+            tokio::select! {
+                msg = rx.recv_async() => match msg {
+                    Ok(state) => latest = state,
+                    Err(_) => return,
+                },
+                _ = timer.tick() => {
+                    latest.resources = resource::lock(&mgr).states();
+                    latest.saved_at = utils::now_ms() as u64;
+                }
+            }
+            let state = latest.clone();
+            match tokio::task::spawn_blocking(move || save(&state)).await {
+                Ok(Ok(())) => log::info!("自动存档完成"),
+                Ok(Err(e)) => log::error!("自动存档失败: {}", e),
+                Err(e) => log::error!("自动存档任务异常: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试独立的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dqwm-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("创建临时目录失败");
+        dir
+    }
+
+    // 新版本存档读不了时，之后的写入不能把它轮换删掉
+    #[test]
+    fn newer_save_survives_writes() {
+        let dir = temp_dir("newer-save");
+        let path = dir.join(SAVE_FILE);
+        let newer = r#"{"version": 99, "state": {}}"#;
+        fs::write(&path, newer).expect("写入测试存档失败");
+        write_atomic(
+            &backup_path(&path),
+            &to_json(&PlayerState::default()).expect("序列化失败"),
+        )
+        .expect("写入测试备份失败");
+
+        assert!(matches!(read_from(&path), Err(SaveError::Version(99))));
+        for _ in 0..2 {
+            assert!(matches!(
+                write_to(&path, &PlayerState::default()),
+                Err(SaveError::Unreadable(_))
+            ));
+        }
+        assert_eq!(fs::read_to_string(&path).ok().as_deref(), Some(newer));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // 旧存档里的完整资源只取数值和结算时间，定义以当前默认资源为准
+    #[test]
+    fn saved_resources_merge_onto_definitions() {
+        let text = r#"{"version": 1, "state": {"resources": [
+            {"id": 1, "name": "Old gold", "description": "", "value": 5000000, "max": 9000000,
+             "base_change": 100, "change": 100, "interval": 10, "last_update_at": 42},
+            {"id": 99, "name": "Removed", "value": 7, "last_update_at": 42}
+        ]}}"#;
+        let state = from_json(text).expect("读取旧存档失败");
+        let mut mgr = DQWMResourceMgr::default();
+        for r in resource::default_resources(1000) {
+            mgr.register(r);
+        }
+        mgr.restore(state.resources);
+
+        let defaults = resource::default_resources(1000);
+        let gold = mgr.get(1).expect("缺少金币");
+        assert_eq!(gold.value, defaults[0].max);
+        assert_eq!(gold.last_update_at, 42);
+        assert_eq!(gold.name, defaults[0].name);
+        assert_eq!(gold.interval, defaults[0].interval);
+        assert_eq!(mgr.get(2), Some(&defaults[1]));
+        assert!(mgr.get(99).is_none());
+    }
+
+    // 损坏的存档改名保留，之后照常存档
+    #[test]
+    fn corrupt_save_is_set_aside() {
+        let dir = temp_dir("corrupt-save");
+        let path = dir.join(SAVE_FILE);
+        let corrupt = "{ not json";
+        fs::write(&path, corrupt).expect("写入测试存档失败");
+
+        assert!(matches!(read_from(&path), Err(SaveError::Json(_))));
+        let state = PlayerState {
+            saved_at: 1,
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            write_to(&path, &state).expect("存档应照常写入");
+        }
+        assert_eq!(read_from(&path).ok().flatten(), Some(state));
+
+        let aside: Vec<String> = fs::read_dir(&dir)
+            .expect("读取临时目录失败")
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("save.json.corrupt-"))
+            .collect();
+        assert_eq!(aside.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.join(&aside[0])).ok().as_deref(),
+            Some(corrupt)
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
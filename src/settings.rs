@@ -0,0 +1,170 @@
+// 设置：保存在存档目录下的 settings.json，运行时由 global::CONFIG 持有
+// 设置页修改后经 CONFIG.apply 写入并通知订阅者，正在运行的系统据此实时更新
+
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use egui::Visuals;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::batttle::BattleSpeed,
+    global::{CONFIG, global_tokio_runtime},
+    save::{self, SaveError},
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+pub const MAX_COLS: usize = 20; // 列数上限的可选最大值
+
+// 战斗动画质量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnimQuality {
+    Off, // 不播放战斗动画
+    Low, // 只有出手和受击，没有飘字
+    #[default]
+    High, // 全部动画
+}
+impl AnimQuality {
+    pub fn next(self) -> Self {
+        match self {
+            AnimQuality::Off => AnimQuality::Low,
+            AnimQuality::Low => AnimQuality::High,
+            AnimQuality::High => AnimQuality::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AnimQuality::Off => "Off",
+            AnimQuality::Low => "Low",
+            AnimQuality::High => "High",
+        }
+    }
+}
+
+// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Chinese,
+}
+impl Language {
+    pub fn next(self) -> Self {
+        match self {
+            Language::English => Language::Chinese,
+            Language::Chinese => Language::English,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Chinese => "Chinese",
+        }
+    }
+}
+
+// 主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+impl Theme {
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    pub fn visuals(self) -> Visuals {
+        match self {
+            Theme::Light => Visuals::light(),
+            Theme::Dark => Visuals::dark(),
+        }
+    }
+}
+
+// 全部设置，缺少的字段取默认值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub max_enemy_cols: usize,     // 敌方最大列数
+    pub max_ally_cols: usize,      // 我方最大列数
+    pub show_fps: bool,            // 显示调试信息
+    pub battle_speed: BattleSpeed, // 战斗速度
+    pub anim_quality: AnimQuality, // 动画质量
+    pub language: Language,        // 语言
+    pub theme: Theme,              // 主题
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_enemy_cols: 10,
+            max_ally_cols: 10,
+            show_fps: cfg!(debug_assertions), // 正式包默认不显示
+            battle_speed: BattleSpeed::default(),
+            anim_quality: AnimQuality::default(),
+            language: Language::default(),
+            theme: Theme::default(),
+        }
+    }
+}
+impl Settings {
+    // 列数限制在 1..=MAX_COLS
+    pub fn sanitized(self) -> Self {
+        Self {
+            max_enemy_cols: self.max_enemy_cols.clamp(1, MAX_COLS),
+            max_ally_cols: self.max_ally_cols.clamp(1, MAX_COLS),
+            ..self
+        }
+    }
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    save::save_dir().map(|d| d.join(SETTINGS_FILE))
+}
+
+// 读设置：文件不存在或损坏时使用默认设置
+pub fn load() -> Settings {
+    let Some(path) = settings_path() else {
+        return Settings::default();
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            log::error!("设置文件格式错误，使用默认设置: {}", e);
+            Settings::default()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Settings::default(),
+        Err(e) => {
+            log::error!("读取设置 {} 失败: {}", path.display(), e);
+            Settings::default()
+        }
+    }
+}
+
+pub fn save(settings: &Settings) -> Result<(), SaveError> {
+    let path = settings_path().ok_or(SaveError::NoSaveDir)?;
+    let text = serde_json::to_string_pretty(settings).map_err(SaveError::Json)?;
+    save::write_atomic(&path, &text)
+}
+
+// 应用设置并在后台保存，各系统通过订阅收到变化
+pub fn update(settings: Settings) {
+    CONFIG.apply(settings);
+    global_tokio_runtime().spawn_blocking(|| {
+        // 写入时取最新设置，连续修改时最后一次写入总是最新值
+        if let Err(e) = save(&CONFIG.settings()) {
+            log::error!("保存设置失败: {}", e);
+        }
+    });
+}
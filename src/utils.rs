@@ -85,3 +85,13 @@ pub fn load_fonts(ctx: &egui::Context, key: &str, font_bytes: &[u8]) {
     // 将最终配置应用到egui上下文
     ctx.set_fonts(fonts);
 }
+
+// 获取当前时间毫秒数
+#[inline]
+pub fn now_ms() -> u128 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}